@@ -0,0 +1,32 @@
+#![feature(test)]
+
+extern crate test;
+
+use cubestore::metastore::scan_bench::{prepare_chunks_db, scan_chunks, scan_chunks_copying};
+use rocksdb::{DB, Options};
+use std::env;
+use test::Bencher;
+
+#[bench]
+fn table_scan_borrowed(b: &mut Bencher) {
+    let path = env::current_dir().unwrap().join("test-table_scan_borrowed");
+    {
+        let db = prepare_chunks_db(&path, 100000).unwrap();
+        b.iter(|| {
+            assert_eq!(scan_chunks(&db).unwrap().len(), 100000);
+        });
+    }
+    let _ = DB::destroy(&Options::default(), path);
+}
+
+#[bench]
+fn table_scan_copying(b: &mut Bencher) {
+    let path = env::current_dir().unwrap().join("test-table_scan_copying");
+    {
+        let db = prepare_chunks_db(&path, 100000).unwrap();
+        b.iter(|| {
+            assert_eq!(scan_chunks_copying(&db).unwrap().len(), 100000);
+        });
+    }
+    let _ = DB::destroy(&Options::default(), path);
+}
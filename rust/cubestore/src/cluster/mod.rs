@@ -517,7 +517,7 @@ mod tests {
         let remote_fs = LocalDirRemoteFs::new(remote, local);
         let chunk_store = Arc::new(MockChunkStore);
         let compaction = Arc::new(MockCompaction);
-        let meta_store = RocksMetaStore::new(&remote_fs.local_file("meta").await.unwrap(), remote_fs.clone());
+        let meta_store = RocksMetaStore::new(&remote_fs.local_file("meta").await.unwrap(), remote_fs.clone()).unwrap();
 
         let foo = ClusterImpl::new(
             "foo".to_string(),
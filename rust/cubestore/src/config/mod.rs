@@ -1,6 +1,6 @@
 use crate::remotefs::{LocalDirRemoteFs, RemoteFs};
 use std::{env, fs};
-use crate::metastore::RocksMetaStore;
+use crate::metastore::{RocksMetaStore, MetaStoreConfig};
 use std::sync::Arc;
 use crate::store::{WALStore, ChunkStore};
 use crate::store::compaction::CompactionServiceImpl;
@@ -39,7 +39,11 @@ impl CubeServices {
     pub async fn start_processing_loops(&self) -> Result<(), CubeError> {
         self.cluster.start_processing_loops().await;
         let meta_store = self.meta_store.clone();
-        tokio::spawn(async move { meta_store.run_upload_loop().await });
+        tokio::spawn(async move {
+            if let Err(e) = meta_store.run_upload_loop().await {
+                log::error!("Metastore upload loop stopped: {}", e);
+            }
+        });
         let scheduler = self.scheduler.clone();
         tokio::spawn(async move { scheduler.run_scheduler().await });
         start_track_event_loop().await;
@@ -72,13 +76,37 @@ pub trait ConfigObj: Send + Sync {
     fn partition_split_threshold(&self) -> u64;
 
     fn select_worker_pool_size(&self) -> usize;
+
+    fn verify_on_start(&self) -> bool;
+
+    fn cache_full_catalog(&self) -> bool;
+
+    fn meta_store_checkpoint_interval_secs(&self) -> u64;
+
+    fn meta_store_snapshot_retention_secs(&self) -> u64;
+
+    fn meta_store_max_concurrent_writes(&self) -> Option<usize>;
+
+    fn meta_store_checkpoint_write_operations(&self) -> Option<u64>;
+
+    fn meta_store_log_compaction_threshold(&self) -> usize;
+
+    fn meta_store_log_deletion_grace_period_secs(&self) -> u64;
 }
 
 pub struct ConfigObjImpl {
     partition_split_threshold: u64,
     data_dir: PathBuf,
     store_provider: FileStoreProvider,
-    select_worker_pool_size: usize
+    select_worker_pool_size: usize,
+    verify_on_start: bool,
+    cache_full_catalog: bool,
+    meta_store_checkpoint_interval_secs: u64,
+    meta_store_snapshot_retention_secs: u64,
+    meta_store_max_concurrent_writes: Option<usize>,
+    meta_store_checkpoint_write_operations: Option<u64>,
+    meta_store_log_compaction_threshold: usize,
+    meta_store_log_deletion_grace_period_secs: u64
 }
 
 impl ConfigObj for ConfigObjImpl {
@@ -89,6 +117,38 @@ impl ConfigObj for ConfigObjImpl {
     fn select_worker_pool_size(&self) -> usize {
         self.select_worker_pool_size
     }
+
+    fn verify_on_start(&self) -> bool {
+        self.verify_on_start
+    }
+
+    fn cache_full_catalog(&self) -> bool {
+        self.cache_full_catalog
+    }
+
+    fn meta_store_checkpoint_interval_secs(&self) -> u64 {
+        self.meta_store_checkpoint_interval_secs
+    }
+
+    fn meta_store_snapshot_retention_secs(&self) -> u64 {
+        self.meta_store_snapshot_retention_secs
+    }
+
+    fn meta_store_max_concurrent_writes(&self) -> Option<usize> {
+        self.meta_store_max_concurrent_writes
+    }
+
+    fn meta_store_checkpoint_write_operations(&self) -> Option<u64> {
+        self.meta_store_checkpoint_write_operations
+    }
+
+    fn meta_store_log_compaction_threshold(&self) -> usize {
+        self.meta_store_log_compaction_threshold
+    }
+
+    fn meta_store_log_deletion_grace_period_secs(&self) -> u64 {
+        self.meta_store_log_deletion_grace_period_secs
+    }
 }
 
 lazy_static! {
@@ -112,7 +172,15 @@ impl Config {
                         FileStoreProvider::Filesystem { remote_dir: env::current_dir().unwrap().join("upstream") }
                     }
                 },
-                select_worker_pool_size: env::var("CUBESTORE_SELECT_WORKERS").ok().map(|v| v.parse::<usize>().unwrap()).unwrap_or(4)
+                select_worker_pool_size: env::var("CUBESTORE_SELECT_WORKERS").ok().map(|v| v.parse::<usize>().unwrap()).unwrap_or(4),
+                verify_on_start: env::var("CUBESTORE_VERIFY_ON_START").ok().map(|v| v.to_lowercase() == "true").unwrap_or(false),
+                cache_full_catalog: env::var("CUBESTORE_CACHE_FULL_CATALOG").ok().map(|v| v.to_lowercase() == "true").unwrap_or(false),
+                meta_store_checkpoint_interval_secs: env::var("CUBESTORE_META_STORE_CHECKPOINT_INTERVAL").ok().map(|v| v.parse::<u64>().unwrap()).unwrap_or(60),
+                meta_store_snapshot_retention_secs: env::var("CUBESTORE_META_STORE_SNAPSHOT_RETENTION").ok().map(|v| v.parse::<u64>().unwrap()).unwrap_or(3 * 60),
+                meta_store_max_concurrent_writes: env::var("CUBESTORE_META_STORE_MAX_CONCURRENT_WRITES").ok().map(|v| v.parse::<usize>().unwrap()),
+                meta_store_checkpoint_write_operations: env::var("CUBESTORE_META_STORE_CHECKPOINT_WRITE_OPERATIONS").ok().map(|v| v.parse::<u64>().unwrap()),
+                meta_store_log_compaction_threshold: env::var("CUBESTORE_META_STORE_LOG_COMPACTION_THRESHOLD").ok().map(|v| v.parse::<usize>().unwrap()).unwrap_or(20),
+                meta_store_log_deletion_grace_period_secs: env::var("CUBESTORE_META_STORE_LOG_DELETION_GRACE_PERIOD").ok().map(|v| v.parse::<u64>().unwrap()).unwrap_or(60)
             })
         }
     }
@@ -123,7 +191,15 @@ impl Config {
                 data_dir: env::current_dir().unwrap().join(format!("{}-local-store", name)),
                 partition_split_threshold: 20,
                 store_provider: FileStoreProvider::Filesystem { remote_dir: env::current_dir().unwrap().join(format!("{}-upstream", name)) },
-                select_worker_pool_size: 0
+                select_worker_pool_size: 0,
+                verify_on_start: false,
+                cache_full_catalog: false,
+                meta_store_checkpoint_interval_secs: 60,
+                meta_store_snapshot_retention_secs: 3 * 60,
+                meta_store_max_concurrent_writes: None,
+                meta_store_checkpoint_write_operations: None,
+                meta_store_log_compaction_threshold: 20,
+                meta_store_log_deletion_grace_period_secs: 60
             })
         }
     }
@@ -200,8 +276,18 @@ impl Config {
         let remote_fs = self.remote_fs().unwrap();
         let (event_sender, event_receiver) = broadcast::channel(10000); // TODO config
 
-        let meta_store = RocksMetaStore::load_from_remote(self.meta_store_path().to_str().unwrap(), remote_fs.clone()).await.unwrap();
+        let meta_store_config = MetaStoreConfig {
+            checkpoint_interval: Duration::from_secs(self.config_obj.meta_store_checkpoint_interval_secs()),
+            snapshot_retention: Duration::from_secs(self.config_obj.meta_store_snapshot_retention_secs()),
+            max_concurrent_writes: self.config_obj.meta_store_max_concurrent_writes(),
+            checkpoint_write_operations: self.config_obj.meta_store_checkpoint_write_operations(),
+            log_compaction_threshold: self.config_obj.meta_store_log_compaction_threshold(),
+            log_deletion_grace_period: Duration::from_secs(self.config_obj.meta_store_log_deletion_grace_period_secs()),
+            ..MetaStoreConfig::default()
+        };
+        let meta_store = RocksMetaStore::load_from_remote(self.meta_store_path().to_str().unwrap(), remote_fs.clone(), meta_store_config, self.config_obj.verify_on_start()).await.unwrap();
         meta_store.add_listener(event_sender).await;
+        meta_store.set_cache_full_catalog(self.config_obj.cache_full_catalog()).await;
         let wal_store = WALStore::new(meta_store.clone(), remote_fs.clone(), 500000);
         let chunk_store = ChunkStore::new(meta_store.clone(), remote_fs.clone(), wal_store.clone(), 262144);
         let compaction_service = CompactionServiceImpl::new(meta_store.clone(), chunk_store.clone(), remote_fs.clone(), self.config_obj.clone());
@@ -39,6 +39,11 @@ impl ImportFormat {
                         row.push(match column.get_column_type() {
                             ColumnType::String => TableValue::String(value.to_string()),
                             ColumnType::Int => TableValue::Int(value.parse()?),
+                            ColumnType::Json => {
+                                serde_json::from_str::<serde_json::Value>(value)
+                                    .map_err(|e| CubeError::user(format!("Invalid JSON value '{}': {}", value, e)))?;
+                                TableValue::String(value.to_string())
+                            }
                             x => panic!("CSV import for {:?} is not implemented", x)
                         });
 
@@ -40,39 +40,85 @@ pub mod telemetry;
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CubeError {
     message: String,
-    cause: CubeErrorCauseType
+    cause: CubeErrorCause
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum CubeErrorCauseType {
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CubeErrorCause {
     User,
-    Internal
+    Internal,
+    NotFound,
+    UniqueViolation,
+    Conflict,
+    Corruption,
+    RemoteStorage,
+    Transient
 }
 
 impl CubeError {
     fn user(message: String) -> CubeError {
         CubeError {
-            message, cause: CubeErrorCauseType::User
+            message, cause: CubeErrorCause::User
         }
     }
 
     fn internal(message: String) -> CubeError {
         CubeError {
-            message, cause: CubeErrorCauseType::Internal
+            message, cause: CubeErrorCause::Internal
+        }
+    }
+
+    fn not_found(message: String) -> CubeError {
+        CubeError {
+            message, cause: CubeErrorCause::NotFound
         }
     }
 
+    fn unique_violation(message: String) -> CubeError {
+        CubeError {
+            message, cause: CubeErrorCause::UniqueViolation
+        }
+    }
+
+    fn conflict(message: String) -> CubeError {
+        CubeError {
+            message, cause: CubeErrorCause::Conflict
+        }
+    }
+
+    fn corruption(message: String) -> CubeError {
+        CubeError {
+            message, cause: CubeErrorCause::Corruption
+        }
+    }
+
+    fn remote_storage(message: String) -> CubeError {
+        CubeError {
+            message, cause: CubeErrorCause::RemoteStorage
+        }
+    }
+
+    fn transient(message: String) -> CubeError {
+        CubeError {
+            message, cause: CubeErrorCause::Transient
+        }
+    }
+
+    pub fn cause(&self) -> CubeErrorCause {
+        self.cause
+    }
+
     fn from_error<E: fmt::Display>(error: E) -> CubeError {
         CubeError {
             message: format!("{}\n{}", error, Backtrace::capture()),
-            cause: CubeErrorCauseType::Internal
+            cause: CubeErrorCause::Internal
         }
     }
 
     fn from_debug_error<E: Debug>(error: E) -> CubeError {
         CubeError {
             message: format!("{:?}\n{}", error, Backtrace::capture()),
-            cause: CubeErrorCauseType::Internal
+            cause: CubeErrorCause::Internal
         }
     }
 }
@@ -139,7 +185,7 @@ impl From<std::time::SystemTimeError> for CubeError {
 
 impl From<tokio::time::Elapsed> for CubeError {
     fn from(v: tokio::time::Elapsed) -> Self {
-        CubeError::internal(v.to_string())
+        CubeError::transient(v.to_string())
     }
 }
 
@@ -193,7 +239,7 @@ impl From<flexbuffers::SerializationError> for CubeError {
 
 impl From<s3::S3Error> for CubeError {
     fn from(v: s3::S3Error) -> Self {
-        CubeError::internal(v.to_string())
+        CubeError::remote_storage(v.to_string())
     }
 }
 
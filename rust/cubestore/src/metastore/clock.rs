@@ -0,0 +1,46 @@
+use std::fmt::Debug;
+use std::time::SystemTime;
+#[cfg(test)]
+use std::sync::Mutex;
+#[cfg(test)]
+use std::time::Duration;
+
+/// Source of the current time for checkpoint scheduling and snapshot retention.
+pub trait Clock: Debug + Send + Sync {
+    fn now(&self) -> SystemTime;
+}
+
+#[derive(Debug)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// Stands still until advanced so time dependent logic can be tested without sleeping.
+#[cfg(test)]
+#[derive(Debug)]
+pub struct TestClock {
+    now: Mutex<SystemTime>
+}
+
+#[cfg(test)]
+impl TestClock {
+    pub fn new(now: SystemTime) -> TestClock {
+        TestClock { now: Mutex::new(now) }
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        let mut now = self.now.lock().unwrap();
+        *now = *now + duration;
+    }
+}
+
+#[cfg(test)]
+impl Clock for TestClock {
+    fn now(&self) -> SystemTime {
+        *self.now.lock().unwrap()
+    }
+}
@@ -1,7 +1,7 @@
 use rocksdb::DB;
 use std::sync::Arc;
 use serde::{Deserialize, Deserializer};
-use super::{BaseRocksSecondaryIndex, RocksTable, IndexId, RocksSecondaryIndex, TableId, Index, Column};
+use super::{BaseRocksSecondaryIndex, RocksTable, IndexId, RocksSecondaryIndex, TableId, Index, Column, IndexDef};
 use byteorder::{BigEndian, WriteBytesExt};
 use std::io::{Cursor, Write};
 use crate::metastore::{MetaStoreEvent, IdRow};
@@ -16,6 +16,10 @@ impl Index {
         &self.name
     }
 
+    pub fn table_id(&self) -> u64 {
+        self.table_id
+    }
+
     pub fn columns(&self) -> &Vec<Column> {
         &self.columns
     }
@@ -28,6 +32,20 @@ impl Index {
     pub fn sort_key_size(&self) -> u64 {
         self.sort_key_size
     }
+
+    // Added columns aren't part of the sort key as existing partitions are already sorted without them
+    pub fn add_column(&self, column: Column) -> Index {
+        let mut index = self.clone();
+        index.columns.push(column.replace_index(self.columns.len()));
+        index
+    }
+
+    pub fn to_index_def(&self) -> IndexDef {
+        IndexDef {
+            name: self.name.to_string(),
+            columns: self.columns.iter().take(self.sort_key_size as usize).map(|c| c.get_name().to_string()).collect()
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -48,6 +66,10 @@ impl BaseRocksSecondaryIndex<Index> for IndexRocksIndex {
     fn is_unique(&self) -> bool {
         RocksSecondaryIndex::is_unique(self)
     }
+
+    fn is_ordered(&self) -> bool {
+        RocksSecondaryIndex::is_ordered(self)
+    }
 }
 
 rocks_table_impl!(
@@ -0,0 +1,135 @@
+use rocksdb::DB;
+use std::sync::Arc;
+use serde::{Deserialize, Deserializer};
+use super::{BaseRocksSecondaryIndex, RocksTable, IndexId, RocksSecondaryIndex, TableId, IndexStats, ColumnStats};
+use byteorder::{BigEndian, WriteBytesExt};
+use crate::metastore::{MetaStoreEvent, IdRow};
+use crate::rocks_table_impl;
+use crate::base_rocks_secondary_index;
+use crate::table::{Row, TableValue};
+
+impl IndexStats {
+    pub fn new(index_id: u64, columns: Vec<ColumnStats>) -> IndexStats {
+        IndexStats { index_id, columns }
+    }
+
+    pub fn from_rows(index_id: u64, column_count: usize, rows: &[Row]) -> IndexStats {
+        let mut columns = vec![ColumnStats::empty(); column_count];
+        for row in rows.iter() {
+            for (stats, value) in columns.iter_mut().zip(row.values().iter()) {
+                stats.add_value(value);
+            }
+        }
+        IndexStats { index_id, columns }
+    }
+
+    pub fn get_index_id(&self) -> u64 {
+        self.index_id
+    }
+
+    pub fn columns(&self) -> &Vec<ColumnStats> {
+        &self.columns
+    }
+
+    pub fn merge(&self, other: &IndexStats) -> IndexStats {
+        let column_count = self.columns.len().max(other.columns.len());
+        let columns = (0..column_count).map(|i| match (self.columns.get(i), other.columns.get(i)) {
+            (Some(a), Some(b)) => a.merge(b),
+            (Some(a), None) => a.clone(),
+            (None, Some(b)) => b.clone(),
+            (None, None) => ColumnStats::empty()
+        }).collect();
+        IndexStats { index_id: self.index_id, columns }
+    }
+}
+
+impl ColumnStats {
+    pub fn new(min: Option<TableValue>, max: Option<TableValue>, null_count: u64) -> ColumnStats {
+        ColumnStats { min, max, null_count }
+    }
+
+    fn empty() -> ColumnStats {
+        ColumnStats { min: None, max: None, null_count: 0 }
+    }
+
+    fn add_value(&mut self, value: &TableValue) {
+        if let TableValue::Null = value {
+            self.null_count += 1;
+            return;
+        }
+        if self.min.as_ref().map(|m| value < m).unwrap_or(true) {
+            self.min = Some(value.clone());
+        }
+        if self.max.as_ref().map(|m| value > m).unwrap_or(true) {
+            self.max = Some(value.clone());
+        }
+    }
+
+    fn merge(&self, other: &ColumnStats) -> ColumnStats {
+        ColumnStats {
+            min: self.min.iter().chain(other.min.iter()).min().cloned(),
+            max: self.max.iter().chain(other.max.iter()).max().cloned(),
+            null_count: self.null_count + other.null_count
+        }
+    }
+
+    pub fn min(&self) -> &Option<TableValue> {
+        &self.min
+    }
+
+    pub fn max(&self) -> &Option<TableValue> {
+        &self.max
+    }
+
+    pub fn null_count(&self) -> u64 {
+        self.null_count
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum IndexStatsRocksIndex {
+    IndexId = 1
+}
+
+rocks_table_impl!(
+    IndexStats,
+    IndexStatsRocksTable,
+    TableId::IndexStats,
+    { vec![Box::new(IndexStatsRocksIndex::IndexId)] },
+    DeleteIndexStats
+);
+
+#[derive(Hash, Clone, Debug)]
+pub enum IndexStatsIndexKey {
+    ByIndexId(u64)
+}
+
+base_rocks_secondary_index!(IndexStats, IndexStatsRocksIndex);
+
+impl RocksSecondaryIndex<IndexStats, IndexStatsIndexKey> for IndexStatsRocksIndex {
+    fn typed_key_by(&self, row: &IndexStats) -> IndexStatsIndexKey {
+        match self {
+            IndexStatsRocksIndex::IndexId => IndexStatsIndexKey::ByIndexId(row.index_id)
+        }
+    }
+
+    fn key_to_bytes(&self, key: &IndexStatsIndexKey) -> Vec<u8> {
+        match key {
+            IndexStatsIndexKey::ByIndexId(index_id) => {
+                let mut buf = Vec::new();
+                buf.write_u64::<BigEndian>(*index_id).unwrap();
+                buf
+            }
+        }
+    }
+
+    fn is_unique(&self) -> bool {
+        match self {
+            IndexStatsRocksIndex::IndexId => true
+        }
+    }
+
+    fn get_id(&self) -> IndexId {
+        *self as IndexId
+    }
+}
@@ -8,6 +8,8 @@ use crate::metastore::{RowKey, MetaStoreEvent, IdRow};
 use crate::base_rocks_secondary_index;
 use crate::rocks_table_impl;
 use chrono::{DateTime, Utc};
+use std::time::Duration;
+use crate::CubeError;
 
 #[derive(Clone, Debug, Serialize, Deserialize, Hash, Eq, PartialEq)]
 pub enum JobType {
@@ -17,6 +19,17 @@ pub enum JobType {
     Repartition
 }
 
+impl JobType {
+    pub fn heart_beat_timeout(&self) -> Duration {
+        match self {
+            JobType::WalPartitioning => Duration::from_secs(120),
+            JobType::PartitionCompaction => Duration::from_secs(600),
+            JobType::TableImport => Duration::from_secs(120),
+            JobType::Repartition => Duration::from_secs(600)
+        }
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug, Hash)]
 pub enum JobStatus {
     Scheduled(String),
@@ -80,6 +93,17 @@ impl Job {
     pub fn completed(&self) -> Job {
         self.update_status(JobStatus::Completed)
     }
+
+    pub fn is_orphaned(&self, now: DateTime<Utc>, heart_beat_timeout: Duration) -> Result<bool, CubeError> {
+        if let JobStatus::ProcessingBy(_) = self.status {
+            let timeout = chrono::Duration::from_std(heart_beat_timeout).map_err(|e| CubeError::internal(format!(
+                "Invalid heart beat timeout for {:?}: {}", self.job_type, e
+            )))?;
+            Ok(self.last_heart_beat + timeout < now)
+        } else {
+            Ok(false)
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -5,17 +5,21 @@ pub mod partition;
 pub mod chunks;
 pub mod wal;
 pub mod job;
+pub mod index_stats;
 pub mod listener;
+pub mod clock;
+#[doc(hidden)]
+pub mod scan_bench;
 
 use std::hash::{Hasher, Hash};
-use std::{io::Cursor, sync::Arc, collections::{hash_map::DefaultHasher}, time, env};
+use std::{io::Cursor, sync::Arc, collections::{hash_map::DefaultHasher}, env};
 use tokio::fs;
-use rocksdb::{DB, WriteBatch, Options, DBIterator, WriteBatchIterator};
-use tokio::sync::{RwLock, Notify};
+use rocksdb::{DB, WriteBatch, Options, DBRawIterator, ReadOptions, WriteBatchIterator, Snapshot};
+use tokio::sync::{RwLock, Notify, Semaphore, mpsc};
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize, Deserializer};
-use log::{error, info};
+use log::{error, info, warn};
 
 use crate::CubeError;
 use schema::{SchemaRocksTable, SchemaRocksIndex};
@@ -24,6 +28,8 @@ use index::{IndexRocksTable, IndexRocksIndex};
 use partition::{PartitionRocksIndex, PartitionRocksTable};
 use chunks::ChunkRocksTable;
 use wal::WALRocksTable;
+use clock::{Clock, SystemClock};
+use index_stats::{IndexStatsRocksTable, IndexStatsRocksIndex, IndexStatsIndexKey};
 use parquet::{basic::{Type, LogicalType}, schema::types};
 use crate::store::DataFrame;
 use crate::table::{Row, TableValue};
@@ -31,8 +37,9 @@ use core::fmt;
 use smallvec::alloc::fmt::Formatter;
 use crate::metastore::index::IndexIndexKey;
 use std::fmt::Debug;
+use tokio::sync::broadcast;
 use tokio::sync::broadcast::Sender;
-use crate::metastore::job::{Job, JobRocksTable, JobRocksIndex, JobIndexKey, JobStatus};
+use crate::metastore::job::{Job, JobRocksTable, JobRocksIndex, JobIndexKey, JobStatus, JobType};
 use crate::metastore::partition::PartitionIndexKey;
 use crate::metastore::chunks::{ChunkRocksIndex, ChunkIndexKey};
 use crate::remotefs::{RemoteFs, LocalDirRemoteFs};
@@ -45,13 +52,21 @@ use itertools::Itertools;
 use arrow::datatypes::TimeUnit::{Microsecond};
 use parquet::basic::Repetition;
 use tokio::fs::File;
-use tokio::time::{Duration};
+use tokio::time::{Duration, Instant};
 use regex::Regex;
 use futures::future::join_all;
 use table::Table;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::cmp::Ordering;
 use crate::metastore::table::{TablePath, TableIndexKey};
 use crate::metastore::wal::{WALIndexKey, WALRocksIndex};
+use crate::table::parquet::ParquetTableStore;
+use crate::table::TableStore;
+use chrono::{DateTime, Utc};
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use std::io::{Read, Write};
 
 #[macro_export]
 macro_rules! format_table_value {
@@ -63,12 +78,12 @@ macro_rules! data_frame_from {
     (
         $( #[$struct_attr:meta] )*
         pub struct $name:ident {
-            $( $variant:ident : $tt:ty ),+
+            $( $( #[$field_attr:meta] )* $variant:ident : $tt:ty ),+
         }
     ) => {
         $( #[$struct_attr] )*
         pub struct $name {
-            $( $variant : $tt ),+
+            $( $( #[$field_attr] )* $variant : $tt ),+
         }
 
         impl From<Vec<IdRow<$name>>> for DataFrame {
@@ -107,6 +122,10 @@ macro_rules! base_rocks_secondary_index {
             fn is_unique(&self) -> bool {
                 RocksSecondaryIndex::is_unique(self)
             }
+
+            fn is_ordered(&self) -> bool {
+                RocksSecondaryIndex::is_ordered(self)
+            }
         }
     }
 }
@@ -208,6 +227,22 @@ impl DataFrameValue<String> for Option<u64> {
     }
 }
 
+impl DataFrameValue<String> for SystemTime {
+    fn value(v: &Self) -> String {
+        DateTime::<Utc>::from(*v).to_rfc3339()
+    }
+}
+
+impl DataFrameValue<String> for Option<(SystemTime, Option<u64>)> {
+    fn value(v: &Self) -> String {
+        v.as_ref().map(|(time, job_id)| format!(
+            "{} by {}",
+            <SystemTime as DataFrameValue<String>>::value(time),
+            <Option<u64> as DataFrameValue<String>>::value(job_id)
+        )).unwrap_or("NULL".to_string())
+    }
+}
+
 impl DataFrameValue<String> for Option<Row> {
     fn value(v: &Self) -> String {
         v.as_ref().map(|v| format!("({})", v.values().iter().map(|tv| match tv {
@@ -229,7 +264,9 @@ pub enum ColumnType {
     Bytes,
     Timestamp,
     Decimal,
-    Boolean
+    Boolean,
+    Json,
+    Float
 }
 
 impl From<&Column> for parquet::schema::types::Type {
@@ -272,6 +309,17 @@ impl From<&Column> for parquet::schema::types::Type {
                     .with_repetition(Repetition::OPTIONAL)
                     .build().unwrap()
             }
+            crate::metastore::ColumnType::Json => {
+                types::Type::primitive_type_builder(&column.get_name(), Type::BYTE_ARRAY)
+                    .with_logical_type(LogicalType::JSON)
+                    .with_repetition(Repetition::OPTIONAL)
+                    .build().unwrap()
+            }
+            crate::metastore::ColumnType::Float => {
+                types::Type::primitive_type_builder(&column.get_name(), Type::DOUBLE)
+                    .with_repetition(Repetition::OPTIONAL)
+                    .build().unwrap()
+            }
         }
     }
 }
@@ -283,19 +331,22 @@ pub struct Column {
     column_index: usize
 }
 
-impl Into<Field> for Column {
-    fn into(self) -> Field {
-        Field::new(
+impl Column {
+    pub fn arrow_field(&self) -> Result<Field, CubeError> {
+        Ok(Field::new(
             self.name.as_str(),
             match self.column_type {
                 ColumnType::String => DataType::Utf8,
                 ColumnType::Int => DataType::Int64,
+                ColumnType::Bytes => DataType::Binary,
                 ColumnType::Timestamp => DataType::Timestamp(Microsecond, None),
                 ColumnType::Boolean => DataType::Boolean,
-                x => panic!("Unimplemented arrow type: {:?}", x)
+                ColumnType::Json => DataType::Utf8,
+                ColumnType::Float => DataType::Float64,
+                ColumnType::Decimal => return Err(CubeError::user(format!("Unimplemented arrow type for column {}: {:?}", self.name, self.column_type)))
             },
             false
-        )
+        ))
     }
 }
 
@@ -306,6 +357,8 @@ impl fmt::Display for Column {
             ColumnType::Int => "INT",
             ColumnType::Timestamp => "TIMESTAMP",
             ColumnType::Boolean => "BOOLEAN",
+            ColumnType::Json => "JSON",
+            ColumnType::Float => "FLOAT",
             x => panic!("TODO: {:?}", x)
         }))
     }
@@ -347,10 +400,20 @@ pub struct Partition {
     min_value: Option<Row>,
     max_value: Option<Row>,
     active: bool,
-    main_table_row_count: u64
+    main_table_row_count: u64,
+    #[serde(default = "unknown_time")]
+    created_at: SystemTime,
+    #[serde(default = "unknown_time")]
+    last_modified: SystemTime,
+    #[serde(default)]
+    last_activation: Option<(SystemTime, Option<u64>)>
 }
 }
 
+fn unknown_time() -> SystemTime {
+    SystemTime::UNIX_EPOCH
+}
+
 data_frame_from! {
 #[derive(Clone, Serialize, Deserialize, Debug, Eq, PartialEq, Hash)]
 pub struct Chunk {
@@ -368,6 +431,20 @@ pub struct WAL {
     uploaded: bool
 }
 
+/// Best-effort statistics of the data that landed in an index. Columns are in index column order.
+#[derive(Clone, Serialize, Deserialize, Debug, Eq, PartialEq, Hash)]
+pub struct IndexStats {
+    index_id: u64,
+    columns: Vec<ColumnStats>
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Eq, PartialEq, Hash)]
+pub struct ColumnStats {
+    min: Option<TableValue>,
+    max: Option<TableValue>,
+    null_count: u64
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug, Eq, PartialEq)]
 pub struct IdRow<T: Clone> {
     id: u64,
@@ -399,7 +476,8 @@ struct KeyVal {
 struct BatchPipe<'a> {
     db: &'a DB,
     write_batch: WriteBatch,
-    events: Vec<MetaStoreEvent>
+    events: Vec<MetaStoreEvent>,
+    coalesced_deletes: Vec<(TableId, u64)>
 }
 
 impl<'a> BatchPipe<'a> {
@@ -407,7 +485,8 @@ impl<'a> BatchPipe<'a> {
         BatchPipe  {
             db,
             write_batch: WriteBatch::default(),
-            events: Vec::new()
+            events: Vec::new(),
+            coalesced_deletes: Vec::new()
         }
     }
 
@@ -419,10 +498,32 @@ impl<'a> BatchPipe<'a> {
         self.events.push(event);
     }
 
+    // Deletes are counted instead of per row events and reported as single DeleteCoalesced on write
+    fn coalesce_deletes(&mut self, table_id: TableId) {
+        if !self.coalesced_deletes.iter().any(|(t, _)| *t == table_id) {
+            self.coalesced_deletes.push((table_id, 0));
+        }
+    }
+
+    fn add_coalesced_delete(&mut self, table_id: TableId) -> bool {
+        if let Some((_, count)) = self.coalesced_deletes.iter_mut().find(|(t, _)| *t == table_id) {
+            *count += 1;
+            true
+        } else {
+            false
+        }
+    }
+
     fn batch_write_rows(self) -> Result<Vec<MetaStoreEvent>, CubeError> {
         let db = self.db;
         db.write(self.write_batch)?;
-        Ok(self.events)
+        let mut events = self.events;
+        for (table_id, count) in self.coalesced_deletes.into_iter() {
+            if count > 0 {
+                events.push(MetaStoreEvent::DeleteCoalesced(table_id, count));
+            }
+        }
+        Ok(events)
     }
 }
 
@@ -434,7 +535,11 @@ pub trait MetaStoreTable: Send + Sync {
 
     async fn row_by_id_or_not_found(&self, id: u64) -> Result<IdRow<Self::T>, CubeError>;
 
+    async fn row_exists(&self, id: u64) -> Result<bool, CubeError>;
+
     async fn insert_row(&self, row: Self::T) -> Result<IdRow<Self::T>, CubeError>;
+
+    async fn insert_rows(&self, rows: Vec<Self::T>) -> Result<Vec<IdRow<Self::T>>, CubeError>;
 }
 
 struct MetaStoreTableImpl<R: RocksTable + 'static, F: Fn(Arc<DB>) -> R + Send + Sync + Clone + 'static> {
@@ -462,20 +567,37 @@ impl<R: RocksTable + 'static, F: Fn(Arc<DB>) -> R + Send + Sync + Clone + 'stati
         }).await
     }
 
+    async fn row_exists(&self, id: u64) -> Result<bool, CubeError> {
+        let table = self.rocks_table_fn.clone();
+        self.rocks_meta_store.read_operation(move |db_ref| {
+            Ok(table(db_ref).exists(id)?)
+        }).await
+    }
+
     async fn insert_row(&self, row: Self::T) -> Result<IdRow<Self::T>, CubeError> {
         let table = self.rocks_table_fn.clone();
         self.rocks_meta_store.write_operation(move |db_ref, batch| {
             Ok(table(db_ref).insert(row, batch)?)
         }).await
     }
+
+    async fn insert_rows(&self, rows: Vec<Self::T>) -> Result<Vec<IdRow<Self::T>>, CubeError> {
+        let table = self.rocks_table_fn.clone();
+        self.rocks_meta_store.write_operation(move |db_ref, batch| {
+            Ok(table(db_ref).insert_many(rows, batch)?)
+        }).await
+    }
 }
 
 #[async_trait]
 pub trait MetaStore: Send + Sync {
     async fn wait_for_current_seq_to_sync(&self) -> Result<(), CubeError>;
+    async fn get_meta_store_stats(&self) -> Result<MetaStoreStats, CubeError>;
     fn schemas_table(&self) -> Box<dyn MetaStoreTable<T=Schema>>;
     async fn create_schema(&self, schema_name: String, if_not_exists: bool) -> Result<IdRow<Schema>, CubeError>;
     async fn get_schemas(&self) -> Result<Vec<IdRow<Schema>>, CubeError>;
+    async fn get_schemas_with_counts(&self) -> Result<Vec<(IdRow<Schema>, u64, u64)>, CubeError>;
+    async fn get_schema_usage(&self, schema_id: u64) -> Result<SchemaUsage, CubeError>;
     async fn get_schema_by_id(&self, schema_id: u64) -> Result<IdRow<Schema>, CubeError>;
     //TODO Option
     async fn get_schema_id(&self, schema_name: String) -> Result<u64, CubeError>;
@@ -487,43 +609,73 @@ pub trait MetaStore: Send + Sync {
     async fn delete_schema_by_id(&self, schema_id: u64) -> Result<(), CubeError>;
 
     fn tables_table(&self) -> Box<dyn MetaStoreTable<T=Table>>;
-    async fn create_table(&self, schema_name: String, table_name: String, columns: Vec<Column>, location: Option<String>, import_format: Option<ImportFormat>, indexes: Vec<IndexDef>) -> Result<IdRow<Table>, CubeError>;
+    async fn create_table(&self, schema_name: String, table_name: String, columns: Vec<Column>, location: Option<String>, import_format: Option<ImportFormat>, indexes: Vec<IndexDef>, initial_partitions: Vec<(Option<Row>, Option<Row>)>) -> Result<IdRow<Table>, CubeError>;
     async fn get_table(&self, schema_name: String, table_name: String) -> Result<IdRow<Table>, CubeError>;
+    async fn resolve_tables(&self, refs: Vec<(String, String)>) -> Result<HashMap<(String, String), IdRow<Table>>, CubeError>;
     async fn get_table_by_id(&self, table_id: u64) -> Result<IdRow<Table>, CubeError>;
     async fn get_tables(&self) -> Result<Vec<IdRow<Table>>, CubeError>;
     async fn get_tables_with_path(&self) -> Result<Vec<TablePath>, CubeError>;
-    async fn drop_table(&self, table_id: u64) -> Result<IdRow<Table>, CubeError>;
+    async fn get_tables_with_path_for_schema(&self, schema_id: u64) -> Result<Vec<TablePath>, CubeError>;
+    async fn get_tables_using_column_type(&self, col_type: ColumnType) -> Result<Vec<IdRow<Table>>, CubeError>;
+    async fn get_table_column_types(&self, table_id: u64) -> Result<Vec<(String, ColumnType)>, CubeError>;
+    async fn drop_table(&self, table_id: u64, coalesce_events: bool) -> Result<IdRow<Table>, CubeError>;
+    async fn add_column(&self, table_id: u64, column: Column) -> Result<IdRow<Table>, CubeError>;
+    async fn truncate_table(&self, table_id: u64) -> Result<(), CubeError>;
 
     fn partition_table(&self) -> Box<dyn MetaStoreTable<T=Partition>>;
     async fn create_partition(&self, partition: Partition) -> Result<IdRow<Partition>, CubeError>;
     async fn get_partition(&self, partition_id: u64) -> Result<IdRow<Partition>, CubeError>;
+    // Full scan of all partitions in the system intended for maintenance tooling only
+    async fn get_all_partitions(&self, active_only: bool) -> Result<Vec<IdRow<Partition>>, CubeError>;
     async fn get_partition_for_compaction(&self, partition_id: u64) -> Result<(IdRow<Partition>, IdRow<Index>), CubeError>;
+    async fn get_compaction_input(&self, partition_id: u64, min_rows: Option<u64>) -> Result<CompactionInput, CubeError>;
     async fn get_partition_chunk_sizes(&self, partition_id: u64) -> Result<u64, CubeError>;
     async fn swap_active_partitions(
         &self,
         current_active: Vec<u64>,
         new_active: Vec<u64>,
         compacted_chunk_ids: Vec<u64>,
-        new_active_min_max: Vec<(u64, (Option<Row>, Option<Row>))>
+        new_active_min_max: Vec<(u64, (Option<Row>, Option<Row>))>,
+        new_active_stats: Option<IndexStats>,
+        job_id: Option<u64>
     ) -> Result<(), CubeError>;
+    async fn delete_partition(&self, partition_id: u64) -> Result<IdRow<Partition>, CubeError>;
 
     fn index_table(&self) -> Box<dyn MetaStoreTable<T=Index>>;
+    async fn delete_index(&self, index_id: u64, force: bool) -> Result<IdRow<Index>, CubeError>;
+    async fn get_index_stats(&self, index_id: u64) -> Result<Option<IdRow<IndexStats>>, CubeError>;
     async fn get_default_index(&self, table_id: u64) -> Result<IdRow<Index>, CubeError>;
+    async fn get_default_index_record_batch_schema(&self, table_id: u64) -> Result<arrow::datatypes::SchemaRef, CubeError>;
     async fn get_table_indexes(&self, table_id: u64) -> Result<Vec<IdRow<Index>>, CubeError>;
+    async fn get_table_index_defs(&self, table_id: u64) -> Result<Vec<IndexDef>, CubeError>;
+    async fn get_create_table_spec(&self, table_id: u64) -> Result<CreateTableSpec, CubeError>;
     async fn get_active_partitions_by_index_id(&self, index_id: u64) -> Result<Vec<IdRow<Partition>>, CubeError>;
+    async fn stream_active_partitions_by_index_id(&self, index_id: u64) -> Result<mpsc::Receiver<Result<IdRow<Partition>, CubeError>>, CubeError>;
+    async fn get_unbounded_partitions(&self, index_id: u64) -> Result<Vec<IdRow<Partition>>, CubeError>;
+    async fn get_active_partitions_for_range(&self, index_id: u64, from: Option<Row>, to: Option<Row>) -> Result<Vec<IdRow<Partition>>, CubeError>;
+    async fn get_partitions_for_key_prefix(&self, index_id: u64, prefix: Row) -> Result<Vec<IdRow<Partition>>, CubeError>;
+    async fn get_index_bounds(&self, index_id: u64) -> Result<(Option<Row>, Option<Row>), CubeError>;
+    async fn find_partition_for_value(&self, index_id: u64, value: Row) -> Result<Option<IdRow<Partition>>, CubeError>;
+    async fn check_partition_coverage(&self, index_id: u64) -> Result<Vec<CoverageIssue>, CubeError>;
 
     fn chunks_table(&self) -> Box<dyn MetaStoreTable<T=Chunk>>;
     async fn create_chunk(&self, partition_id: u64, row_count: usize) -> Result<IdRow<Chunk>, CubeError>;
     async fn get_chunk(&self, chunk_id: u64) -> Result<IdRow<Chunk>, CubeError>;
     async fn get_chunks_by_partition(&self, partition_id: u64) -> Result<Vec<IdRow<Chunk>>, CubeError>;
+    async fn get_chunks_by_partitions(&self, partition_ids: Vec<u64>) -> Result<HashMap<u64, Vec<IdRow<Chunk>>>, CubeError>;
+    async fn get_oldest_chunks_by_partition(&self, partition_id: u64, limit: usize) -> Result<Vec<IdRow<Chunk>>, CubeError>;
     async fn chunk_uploaded(&self, chunk_id: u64) -> Result<IdRow<Chunk>, CubeError>;
     async fn deactivate_chunk(&self, chunk_id: u64) -> Result<(), CubeError>;
+    async fn get_referenced_remote_files(&self) -> Result<HashSet<String>, CubeError>;
 
     async fn create_wal(&self, table_id: u64, row_count: usize) -> Result<IdRow<WAL>, CubeError>;
     async fn get_wal(&self, wal_id: u64) -> Result<IdRow<WAL>, CubeError>;
     async fn delete_wal(&self, wal_id: u64) -> Result<(), CubeError>;
     async fn wal_uploaded(&self, wal_id: u64) -> Result<IdRow<WAL>, CubeError>;
+    async fn wal_flushed(&self, wal_id: u64, new_chunks: Vec<(u64, usize)>) -> Result<Vec<IdRow<Chunk>>, CubeError>;
     async fn get_wals_for_table(&self, table_id: u64) -> Result<Vec<IdRow<WAL>>, CubeError>;
+    async fn get_all_wals(&self) -> Result<Vec<IdRow<WAL>>, CubeError>;
+    async fn get_all_wals_pending_upload(&self) -> Result<Vec<IdRow<WAL>>, CubeError>;
 
     async fn add_job(&self, job: Job) -> Result<Option<IdRow<Job>>, CubeError>;
     async fn get_job(&self, job_id: u64) -> Result<IdRow<Job>, CubeError>;
@@ -531,6 +683,51 @@ pub trait MetaStore: Send + Sync {
     async fn start_processing_job(&self, server_name: String) -> Result<Option<IdRow<Job>>, CubeError>;
     async fn update_status(&self, job_id: u64, status: JobStatus) -> Result<IdRow<Job>, CubeError>;
     async fn update_heart_beat(&self, job_id: u64) -> Result<IdRow<Job>, CubeError>;
+    async fn update_heart_beats(&self, job_ids: Vec<u64>) -> Result<(), CubeError>;
+    async fn find_orphaned_jobs(&self) -> Result<Vec<IdRow<Job>>, CubeError>;
+    async fn get_jobs_for_table(&self, table_id: u64) -> Result<Vec<IdRow<Job>>, CubeError>;
+    async fn wait_for_table_jobs(&self, table_id: u64, timeout: Duration) -> Result<(), CubeError>;
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CompactionInput {
+    Compact(IdRow<Partition>, IdRow<Index>, Vec<IdRow<Chunk>>),
+    NotWorthCompacting { chunks_row_count: u64 }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MetaStoreStats {
+    pub row_counts: HashMap<TableId, u64>,
+    pub latest_sequence_number: u64,
+    pub last_upload_seq: u64,
+    pub last_check_seq: u64,
+    // Write batches applied locally but not uploaded to remote logs yet
+    pub pending_updates: u64
+}
+
+// Inputs of create_table that reproduce an existing table, initial partitions aside
+#[derive(Clone, Debug, PartialEq)]
+pub struct CreateTableSpec {
+    pub schema_name: String,
+    pub table_name: String,
+    pub columns: Vec<Column>,
+    pub location: Option<String>,
+    pub import_format: Option<ImportFormat>,
+    pub indexes: Vec<IndexDef>
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CoverageIssue {
+    // Bounds are those of the neighbouring partitions, None stands for the open end of the key space
+    Gap { from: Option<Row>, to: Option<Row> },
+    Overlap { first_partition_id: u64, second_partition_id: u64 }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SchemaUsage {
+    pub table_count: u64,
+    // Rows of default indexes: compacted into active partitions plus pending in active chunks
+    pub total_rows: u64
 }
 
 #[derive(Clone, Debug)]
@@ -545,6 +742,10 @@ pub enum MetaStoreEvent {
     DeleteSchema(IdRow<Schema>),
     DeleteTable(IdRow<Table>),
     DeleteWal(IdRow<WAL>),
+    DeleteIndexStats(IdRow<IndexStats>),
+    DeleteCoalesced(TableId, u64),
+    // Sent instead of Update for the schema row so path caches can swap names without reloading
+    SchemaRenamed { id: u64, old_name: String, new_name: String },
 }
 
 type SecondaryKey =  Vec<u8>;
@@ -561,31 +762,54 @@ pub fn get_fixed_prefix() -> usize {
     13
 }
 
+// Number of one-time data migrations applied by RocksMetaStore::backfill_indexes
+const METASTORE_MIGRATION_VERSION: u64 = 2;
+
+// Sequence key of table id 0 which no table uses
+fn migration_version_key() -> [u8; 5] {
+    [2, 0, 0, 0, 0]
+}
+
 impl RowKey {
-    fn from_bytes(bytes: &[u8]) -> RowKey {
+    fn from_bytes(bytes: &[u8]) -> Result<RowKey, CubeError> {
+        Self::read_from_bytes(bytes).map_err(
+            |e| CubeError::corruption(format!("Can't decode row key {:?}: {}", bytes, e))
+        )
+    }
+
+    fn read_from_bytes(bytes: &[u8]) -> Result<RowKey, std::io::Error> {
         let mut reader = Cursor::new(bytes);
-        match reader.read_u8().unwrap() {
-            1 => RowKey::Table(TableId::from(reader.read_u32::<BigEndian>().unwrap()), {
+        match reader.read_u8()? {
+            1 => Ok(RowKey::Table(Self::read_table_id(&mut reader)?, {
                 // skip zero for fixed key padding
-                reader.read_u64::<BigEndian>().unwrap();
-                reader.read_u64::<BigEndian>().unwrap()
-            }),
-            2 => RowKey::Sequence(TableId::from(reader.read_u32::<BigEndian>().unwrap())),
+                reader.read_u64::<BigEndian>()?;
+                reader.read_u64::<BigEndian>()?
+            })),
+            2 => Ok(RowKey::Sequence(Self::read_table_id(&mut reader)?)),
             3 => {
-                let table_id = IndexId::from(reader.read_u32::<BigEndian>().unwrap());
+                let table_id = IndexId::from(reader.read_u32::<BigEndian>()?);
                 let mut secondary_key: SecondaryKey = SecondaryKey::new();
-                let sc_length = bytes.len() - 13;
+                let sc_length = bytes.len().checked_sub(13).ok_or(
+                    std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "secondary index key is too short")
+                )?;
                 for _i in 0..sc_length {
-                    secondary_key.push(reader.read_u8().unwrap());
+                    secondary_key.push(reader.read_u8()?);
                 }
-                let row_id = reader.read_u64::<BigEndian>().unwrap();
+                let row_id = reader.read_u64::<BigEndian>()?;
 
-                RowKey::SecondaryIndex(table_id, secondary_key, row_id)
+                Ok(RowKey::SecondaryIndex(table_id, secondary_key, row_id))
                 },
-            v => panic!("Unknown key prefix: {}", v)
+            v => Err(std::io::Error::new(std::io::ErrorKind::InvalidData, format!("Unknown key prefix: {}", v)))
         }
     }
 
+    fn read_table_id(reader: &mut Cursor<&[u8]>) -> Result<TableId, std::io::Error> {
+        let id = reader.read_u32::<BigEndian>()?;
+        TableId::from_u32(id).ok_or(
+            std::io::Error::new(std::io::ErrorKind::InvalidData, format!("Unknown TableId: {}", id))
+        )
+    }
+
     fn to_bytes(&self) -> Vec<u8> {
         let mut wtr = vec![];
         match self {
@@ -614,15 +838,21 @@ impl RowKey {
 
 macro_rules! enum_from_primitive_impl {
     ($name:ident, $( $variant:ident )*) => {
-        impl From<u32> for $name {
-            fn from(n: u32) -> Self {
+        impl $name {
+            pub fn from_u32(n: u32) -> Option<Self> {
                 $( if n == $name::$variant as u32 {
-                    $name::$variant
+                    Some($name::$variant)
                 } else )* {
-                    panic!("Unknown {}: {}", stringify!($name), n);
+                    None
                 }
             }
         }
+
+        impl From<u32> for $name {
+            fn from(n: u32) -> Self {
+                $name::from_u32(n).unwrap_or_else(|| panic!("Unknown {}: {}", stringify!($name), n))
+            }
+        }
     };
 }
 
@@ -642,6 +872,7 @@ macro_rules! enum_from_primitive {
     };
 }
 
+// Discriminants are persisted as RowKey prefixes: never reorder or reuse them, only append new ones
 enum_from_primitive! {
     #[derive(Copy, Clone, Eq, PartialEq, Debug, Serialize, Deserialize, Hash)]
     pub enum TableId {
@@ -651,21 +882,91 @@ enum_from_primitive! {
         Partitions = 0x0400,
         Chunks = 0x0500,
         WALs = 0x0600,
-        Jobs = 0x0700
+        Jobs = 0x0700,
+        IndexStats = 0x0800
+    }
+}
+
+const ALL_TABLE_IDS: [TableId; 8] = [
+    TableId::Schemas,
+    TableId::Tables,
+    TableId::Indexes,
+    TableId::Partitions,
+    TableId::Chunks,
+    TableId::WALs,
+    TableId::Jobs,
+    TableId::IndexStats
+];
+
+impl TableId {
+    /// Index ids are table id plus index number, see `RocksTable::index_id`.
+    pub fn from_index_id(index_id: IndexId) -> Option<TableId> {
+        TableId::from_u32(index_id - index_id % 0x100)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MetaStoreConfig {
+    pub checkpoint_interval: Duration,
+    pub snapshot_retention: Duration,
+    /// Log files uploaded since the last compaction are merged into one once there are more than this many.
+    pub log_compaction_threshold: usize,
+    /// Merged log files are deleted only after this long so readers that already listed them can still download them.
+    pub log_deletion_grace_period: Duration,
+    /// Excess write operations wait for a permit instead of all piling onto blocking threads. Unbounded if `None`, must not be zero.
+    pub max_concurrent_writes: Option<usize>,
+    /// Checkpoint after this many write operations even if `checkpoint_interval` hasn't passed yet. Time only if `None`.
+    pub checkpoint_write_operations: Option<u64>,
+    /// Overrides `JobType::heart_beat_timeout` for the listed job types when looking for orphaned jobs.
+    pub heart_beat_timeouts: HashMap<JobType, Duration>,
+    pub clock: Arc<dyn Clock>
+}
+
+impl MetaStoreConfig {
+    pub fn heart_beat_timeout(&self, job_type: &JobType) -> Duration {
+        self.heart_beat_timeouts.get(job_type).cloned().unwrap_or_else(|| job_type.heart_beat_timeout())
+    }
+}
+
+impl Default for MetaStoreConfig {
+    fn default() -> Self {
+        MetaStoreConfig {
+            checkpoint_interval: Duration::from_secs(60),
+            snapshot_retention: Duration::from_secs(3 * 60),
+            log_compaction_threshold: 20,
+            log_deletion_grace_period: Duration::from_secs(60),
+            max_concurrent_writes: None,
+            checkpoint_write_operations: None,
+            heart_beat_timeouts: HashMap::new(),
+            clock: Arc::new(SystemClock)
+        }
     }
 }
 
 #[derive(Clone)]
 pub struct RocksMetaStore {
     pub db: Arc<RwLock<Arc<DB>>>,
+    config: MetaStoreConfig,
     listeners: Arc<RwLock<Vec<Sender<MetaStoreEvent>>>>,
+    listener_dropped_counts: Arc<RwLock<Vec<u64>>>,
     remote_fs: Arc<dyn RemoteFs>,
     last_checkpoint_time: Arc<RwLock<SystemTime>>,
     write_notify: Arc<Notify>,
     write_completed_notify: Arc<Notify>,
     last_upload_seq: Arc<RwLock<u64>>,
     last_check_seq: Arc<RwLock<u64>>,
-    upload_loop_enabled: Arc<RwLock<bool>>
+    last_applied_log_seq: Arc<RwLock<u64>>,
+    upload_loop_enabled: Arc<RwLock<bool>>,
+    follower_loop_enabled: Arc<RwLock<bool>>,
+    followed_checkpoint: Arc<RwLock<Option<String>>>,
+    cache_full_catalog: Arc<RwLock<bool>>,
+    catalog_cache: Arc<RwLock<(u64, Option<Arc<CatalogCache>>)>>,
+    write_semaphore: Option<Arc<Semaphore>>,
+    writes_since_checkpoint: Arc<RwLock<u64>>,
+    uncompacted_logs: Arc<RwLock<Vec<String>>>,
+    pending_log_deletions: Arc<RwLock<Vec<(SystemTime, String)>>>,
+    // Followers only replay the primary's logs and reject writes of their own
+    follower: bool
 }
 
 trait BaseRocksSecondaryIndex<T>: Debug {
@@ -684,7 +985,17 @@ trait BaseRocksSecondaryIndex<T>: Debug {
         hasher.finish()
     }
 
+    fn key_prefix(&self, row: &T) -> Vec<u8> {
+        if self.is_ordered() {
+            self.index_key_by(row)
+        } else {
+            self.key_hash(row).to_be_bytes().to_vec()
+        }
+    }
+
     fn is_unique(&self) -> bool;
+
+    fn is_ordered(&self) -> bool;
 }
 
 trait RocksSecondaryIndex<T, K: Hash> : BaseRocksSecondaryIndex<T> {
@@ -704,6 +1015,12 @@ trait RocksSecondaryIndex<T, K: Hash> : BaseRocksSecondaryIndex<T> {
     fn get_id(&self) -> u32;
 
     fn is_unique(&self) -> bool;
+
+    /// Ordered indexes store `key_to_bytes` as is instead of its hash so they can be range scanned.
+    /// Their key bytes must be prefix-free and compare bytewise in the same order as the keys.
+    fn is_ordered(&self) -> bool {
+        false
+    }
 }
 
 impl<T, I> BaseRocksSecondaryIndex<T> for I where I: RocksSecondaryIndex<T, String> {
@@ -718,12 +1035,16 @@ impl<T, I> BaseRocksSecondaryIndex<T> for I where I: RocksSecondaryIndex<T, Stri
     fn is_unique(&self) -> bool {
         RocksSecondaryIndex::is_unique(self)
     }
+
+    fn is_ordered(&self) -> bool {
+        RocksSecondaryIndex::is_ordered(self)
+    }
 }
 
 struct TableScanIter<'a, RT: RocksTable + ?Sized> {
     table_id: TableId,
     table: &'a RT,
-    iter: DBIterator<'a>
+    iter: DBRawIterator<'a>
 }
 
 impl<'a, RT: RocksTable<T=T> + ?Sized, T> Iterator for TableScanIter<'a, RT>
@@ -732,19 +1053,26 @@ impl<'a, RT: RocksTable<T=T> + ?Sized, T> Iterator for TableScanIter<'a, RT>
     type Item = Result<IdRow<T>, CubeError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let option = self.iter.next();
-        if let Some((key, value)) = option {
-            if let RowKey::Table(table_id, row_id) = RowKey::from_bytes(&key) {
-                if table_id != self.table_id {
-                    return None;
-                }
-                Some(self.table.deserialize_id_row(row_id, &value))
-            } else {
-                None
+        if !self.iter.valid() {
+            return None;
+        }
+        // Deserialize straight from iterator's borrowed slices instead of copying every key-value pair
+        let res = if let (Some(key), Some(value)) = (self.iter.key(), self.iter.value()) {
+            match RowKey::from_bytes(key) {
+                Ok(RowKey::Table(table_id, row_id)) => {
+                    if table_id != self.table_id {
+                        return None;
+                    }
+                    Some(self.table.deserialize_id_row(row_id, value))
+                },
+                Ok(_) => None,
+                Err(e) => Some(Err(e))
             }
         } else {
             None
-        }
+        };
+        self.iter.next();
+        res
     }
 }
 
@@ -765,11 +1093,13 @@ trait RocksTable: Debug + Send + Sync + Clone {
         let serialized_row = ser.take_buffer();
 
         for index in Self::indexes().iter() {
-            let hash = index.key_hash(&row);
+            if !index.is_unique() {
+                continue;
+            }
             let index_val = index.index_key_by(&row);
-            let existing_keys = self.get_row_from_index(index.get_id(), &index_val, &hash.to_be_bytes().to_vec())?;
-            if index.is_unique() && existing_keys.len() > 0 {
-                return Err(CubeError::user(
+            let existing_keys = self.get_row_ids_by_index_val(index.get_id(), &index_val, &index.key_prefix(&row), index.is_ordered(), true)?;
+            if existing_keys.len() > 0 {
+                return Err(CubeError::unique_violation(
                     format!(
                         "Unique constraint violation: row {:?} has a key that already exists in {:?} index",
                         &row,
@@ -791,16 +1121,72 @@ trait RocksTable: Debug + Send + Sync + Clone {
         Ok(IdRow::new(row_id, row))
     }
 
+    fn insert_many(&self, rows: Vec<Self::T>, batch_pipe: &mut BatchPipe) -> Result<Vec<IdRow<Self::T>>, CubeError> {
+        // Rows of the same batch aren't visible to the DB lookup in insert so check them against each other first
+        let mut batch_keys = HashSet::new();
+        for row in rows.iter() {
+            for index in Self::indexes().iter().filter(|i| i.is_unique()) {
+                if !batch_keys.insert((index.get_id(), index.index_key_by(row))) {
+                    return Err(CubeError::unique_violation(
+                        format!(
+                            "Unique constraint violation: row {:?} has a key that is duplicated within the batch in {:?} index",
+                            row,
+                            index
+                        )
+                    ))
+                }
+            }
+        }
+
+        let mut res = Vec::with_capacity(rows.len());
+        for row in rows.into_iter() {
+            res.push(self.insert(row, batch_pipe)?);
+        }
+        Ok(res)
+    }
+
     fn get_row_ids_by_index<K: Debug>(&self, row_key: &K, secondary_index: &impl RocksSecondaryIndex<Self::T, K>) -> Result<Vec<u64>, CubeError>
         where K: Hash
     {
-        let hash = secondary_index.typed_key_hash(&row_key);
         let index_val = secondary_index.key_to_bytes(&row_key);
-        let existing_keys = self.get_row_from_index(RocksSecondaryIndex::get_id(secondary_index), &index_val, &hash.to_be_bytes().to_vec())?;
+        let is_ordered = RocksSecondaryIndex::is_ordered(secondary_index);
+        let key_prefix = if is_ordered {
+            index_val.clone()
+        } else {
+            secondary_index.typed_key_hash(&row_key).to_be_bytes().to_vec()
+        };
+        // Scans past the first hit even for unique indexes so get_rows_by_index can report duplicates
+        let existing_keys = self.get_row_ids_by_index_val(
+            RocksSecondaryIndex::get_id(secondary_index),
+            &index_val,
+            &key_prefix,
+            is_ordered,
+            false
+        )?;
 
         Ok(existing_keys)
     }
 
+    fn get_rows_by_index_range<K: Debug>(&self, from: &K, to: &K, secondary_index: &impl RocksSecondaryIndex<Self::T, K>) -> Result<Vec<IdRow<Self::T>>, CubeError>
+        where K: Hash
+    {
+        if !RocksSecondaryIndex::is_ordered(secondary_index) {
+            return Err(CubeError::internal(format!("Range scan requested for unordered {:?} index of {:?} table", secondary_index, self)));
+        }
+        let row_ids = self.get_row_from_ordered_index(
+            RocksSecondaryIndex::get_id(secondary_index),
+            &secondary_index.key_to_bytes(from),
+            &secondary_index.key_to_bytes(to),
+            false
+        )?;
+
+        let mut res = Vec::with_capacity(row_ids.len());
+        for id in row_ids {
+            res.push(self.get_row(id)?.ok_or(CubeError::corruption(format!("Row exists in secondary index however missing in {:?} table: {}", self, id)))?)
+        }
+        Ok(res)
+    }
+
     fn get_rows_by_index<K: Debug>(&self, row_key: &K, secondary_index: &impl RocksSecondaryIndex<Self::T, K>) -> Result<Vec<IdRow<Self::T>>, CubeError>
         where K: Hash
     {
@@ -809,11 +1195,11 @@ trait RocksTable: Debug + Send + Sync + Clone {
         let mut res = Vec::new();
 
         for id in row_ids {
-            res.push(self.get_row(id)?.ok_or(CubeError::internal(format!("Row exists in secondary index however missing in {:?} table: {}", self, id)))?)
+            res.push(self.get_row(id)?.ok_or(CubeError::corruption(format!("Row exists in secondary index however missing in {:?} table: {}", self, id)))?)
         }
 
         if RocksSecondaryIndex::is_unique(secondary_index) && res.len() > 1 {
-            return Err(CubeError::internal(format!("Unique index expected but found multiple values in {:?} table: {:?}", self, res)));
+            return Err(CubeError::corruption(format!("Unique index expected but found multiple values in {:?} table: {:?}", self, res)));
         }
 
         Ok(res)
@@ -824,7 +1210,7 @@ trait RocksTable: Debug + Send + Sync + Clone {
     {
         let rows = self.get_rows_by_index(row_key, secondary_index)?;
         Ok(rows.into_iter().nth(0).ok_or(
-            CubeError::internal(format!("One value expected in {:?} for {:?} but nothing found", self, row_key))
+            CubeError::not_found(format!("One value expected in {:?} for {:?} but nothing found", self, row_key))
         )?)
     }
 
@@ -835,6 +1221,11 @@ trait RocksTable: Debug + Send + Sync + Clone {
     }
 
     fn update(&self, row_id: u64, new_row: Self::T, old_row: &Self::T, batch_pipe: &mut BatchPipe) -> Result<IdRow<Self::T>, CubeError> {
+        self.update_with_event(row_id, new_row, old_row, MetaStoreEvent::Update(self.table_id(), row_id), batch_pipe)
+    }
+
+    // For updates that have a more specific event than MetaStoreEvent::Update, e.g. schema renames
+    fn update_with_event(&self, row_id: u64, new_row: Self::T, old_row: &Self::T, event: MetaStoreEvent, batch_pipe: &mut BatchPipe) -> Result<IdRow<Self::T>, CubeError> {
         let deleted_row = self.delete_index_row(&old_row, row_id)?;
         for row in deleted_row {
             batch_pipe.batch().delete(row.key);
@@ -845,7 +1236,7 @@ trait RocksTable: Debug + Send + Sync + Clone {
         let serialized_row = ser.take_buffer();
 
         let updated_row = self.update_row(row_id, serialized_row)?;
-        batch_pipe.add_event(MetaStoreEvent::Update(self.table_id(), row_id));
+        batch_pipe.add_event(event);
         batch_pipe.batch().put(updated_row.key, updated_row.val);
 
         let index_row = self.insert_index_row(&new_row, row_id)?;
@@ -855,11 +1246,32 @@ trait RocksTable: Debug + Send + Sync + Clone {
         Ok(IdRow::new(row_id, new_row))
     }
 
+    // update_fn must leave every indexed field as is: only the row value is rewritten and secondary index entries are kept
+    fn update_unindexed_with_fn(&self, row_id: u64, update_fn: impl FnOnce(&Self::T) -> Self::T, batch_pipe: &mut BatchPipe) -> Result<IdRow<Self::T>, CubeError> {
+        let row = self.get_row_or_not_found(row_id)?;
+        let new_row = update_fn(&row.get_row());
+        debug_assert_eq!(
+            self.insert_index_row(&new_row, row_id)?.into_iter().map(|kv| kv.key).collect::<Vec<_>>(),
+            self.insert_index_row(&row.get_row(), row_id)?.into_iter().map(|kv| kv.key).collect::<Vec<_>>()
+        );
+
+        let mut ser = flexbuffers::FlexbufferSerializer::new();
+        new_row.serialize(&mut ser).unwrap();
+        let serialized_row = ser.take_buffer();
+
+        let updated_row = self.update_row(row_id, serialized_row)?;
+        batch_pipe.add_event(MetaStoreEvent::Update(self.table_id(), row_id));
+        batch_pipe.batch().put(updated_row.key, updated_row.val);
+        Ok(IdRow::new(row_id, new_row))
+    }
+
     fn delete(&self, row_id: u64, batch_pipe: &mut BatchPipe) -> Result<IdRow<Self::T>, CubeError> {
         let row = self.get_row_or_not_found(row_id)?;
         let deleted_row = self.delete_index_row(row.get_row(), row_id)?;
-        batch_pipe.add_event(MetaStoreEvent::Delete(self.table_id(), row_id));
-        batch_pipe.add_event(self.delete_event(row.clone()));
+        if !batch_pipe.add_coalesced_delete(self.table_id()) {
+            batch_pipe.add_event(MetaStoreEvent::Delete(self.table_id(), row_id));
+            batch_pipe.add_event(self.delete_event(row.clone()));
+        }
         for row in deleted_row {
             batch_pipe.batch().delete(row.key);
         }
@@ -911,33 +1323,41 @@ trait RocksTable: Debug + Send + Sync + Clone {
 
     fn get_row_or_not_found(&self, row_id: u64) -> Result<IdRow<Self::T>, CubeError> {
         self.get_row(row_id)?
-            .ok_or(CubeError::user(format!("Row with id {} is not found for {:?}", row_id, self)))
+            .ok_or(CubeError::not_found(format!("Row with id {} is not found for {:?}", row_id, self)))
     }
 
     fn get_row(&self, row_id: u64) -> Result<Option<IdRow<Self::T>>, CubeError> {
         let ref db = self.db();
-        let res = db.get(RowKey::Table(self.table_id(), row_id).to_bytes())?;
+        let res = db.get_pinned(RowKey::Table(self.table_id(), row_id).to_bytes())?;
 
         if let Some(buffer) = res {
-            let row = self.deserialize_id_row(row_id, buffer.as_slice())?;
+            let row = self.deserialize_id_row(row_id, buffer.as_ref())?;
             return Ok(Some(row));
         }
 
         Ok(None)
     }
 
+    fn exists(&self, row_id: u64) -> Result<bool, CubeError> {
+        let ref db = self.db();
+        Ok(db.get_pinned(RowKey::Table(self.table_id(), row_id).to_bytes())?.is_some())
+    }
+
     fn deserialize_id_row(&self, row_id: u64, buffer: &[u8]) -> Result<IdRow<Self::T>, CubeError> {
-        let r = flexbuffers::Reader::get_root(&buffer).unwrap();
-        let row = self.deserialize_row(r)?;
+        let r = flexbuffers::Reader::get_root(&buffer).map_err(|e| CubeError::corruption(format!(
+            "Can't read row {} of {:?}: {:?}", row_id, self.table_id(), e
+        )))?;
+        let row = self.deserialize_row(r).map_err(|e| CubeError::corruption(format!(
+            "Can't deserialize row {} of {:?}: {}", row_id, self.table_id(), e
+        )))?;
         return Ok(IdRow::new(row_id, row))
     }
 
     fn insert_index_row(&self, row: &Self::T, row_id: u64) -> Result<Vec<KeyVal>, CubeError> {
         let mut res = Vec::new();
         for index in Self::indexes().iter() {
-            let hash = index.key_hash(&row);
             let index_val = index.index_key_by(&row);
-            let key = RowKey::SecondaryIndex(self.index_id( index.get_id()), hash.to_be_bytes().to_vec(), row_id);
+            let key = RowKey::SecondaryIndex(self.index_id( index.get_id()), index.key_prefix(&row), row_id);
             res.push( KeyVal {key: key.to_bytes(),
                               val: index_val});
         }
@@ -947,8 +1367,7 @@ trait RocksTable: Debug + Send + Sync + Clone {
     fn delete_index_row(&self, row: &Self::T, row_id: u64) -> Result<Vec<KeyVal>, CubeError> {
         let mut res = Vec::new();
         for index in Self::indexes().iter() {
-            let hash = index.key_hash(&row);
-            let key = RowKey::SecondaryIndex(self.index_id(index.get_id()), hash.to_be_bytes().to_vec(), row_id);
+            let key = RowKey::SecondaryIndex(self.index_id(index.get_id()), index.key_prefix(&row), row_id);
             res.push( KeyVal {key: key.to_bytes(),
                               val: vec![]});
         }
@@ -956,16 +1375,50 @@ trait RocksTable: Debug + Send + Sync + Clone {
         Ok(res)
     }
 
-    fn get_row_from_index(&self, secondary_id: u32, secondary_key_val: &Vec<u8>, secondary_key_hash: &Vec<u8>) -> Result<Vec<u64>, CubeError> {
+    fn get_row_from_index(&self, secondary_id: u32, secondary_key_val: &Vec<u8>, secondary_key_hash: &Vec<u8>, expect_unique: bool) -> Result<Vec<u64>, CubeError> {
+        let mut res: Vec<u64> = Vec::with_capacity(if expect_unique { 1 } else { 16 });
+        self.scan_row_ids_from_index(secondary_id, secondary_key_val, secondary_key_hash, |row_id| {
+            res.push(row_id);
+            Ok(!expect_unique)
+        })?;
+        Ok(res)
+    }
+
+    fn scan_rows_by_index<K: Debug>(
+        &self,
+        row_key: &K,
+        secondary_index: &impl RocksSecondaryIndex<Self::T, K>,
+        mut f: impl FnMut(IdRow<Self::T>) -> Result<bool, CubeError>
+    ) -> Result<(), CubeError>
+        where K: Hash
+    {
+        if RocksSecondaryIndex::is_ordered(secondary_index) {
+            return Err(CubeError::internal(format!("Lazy scan requested for ordered {:?} index of {:?} table", secondary_index, self)));
+        }
+        let index_val = secondary_index.key_to_bytes(&row_key);
+        let key_hash = secondary_index.typed_key_hash(&row_key).to_be_bytes().to_vec();
+        self.scan_row_ids_from_index(RocksSecondaryIndex::get_id(secondary_index), &index_val, &key_hash, |row_id| {
+            let row = self.get_row(row_id)?.ok_or(CubeError::corruption(format!("Row exists in secondary index however missing in {:?} table: {}", self, row_id)))?;
+            f(row)
+        })
+    }
+
+    // Calls f for every row id matching the key until it returns false
+    fn scan_row_ids_from_index(
+        &self,
+        secondary_id: u32,
+        secondary_key_val: &Vec<u8>,
+        secondary_key_hash: &Vec<u8>,
+        mut f: impl FnMut(u64) -> Result<bool, CubeError>
+    ) -> Result<(), CubeError> {
         let ref db = self.db();
         let key_len = secondary_key_hash.len();
         let key_min = RowKey::SecondaryIndex(self.index_id(secondary_id), secondary_key_hash.clone(), 0);
 
-        let mut res: Vec<u64> = Vec::new();
         let iter = db.prefix_iterator(&key_min.to_bytes()[0..(key_len+5)]);
 
         for (key, value) in iter {
-            if let RowKey::SecondaryIndex(_, secondary_index_hash, row_id) = RowKey::from_bytes(&key) {
+            if let RowKey::SecondaryIndex(_, secondary_index_hash, row_id) = RowKey::from_bytes(&key)? {
 
                 if !secondary_index_hash.iter().zip(secondary_key_hash).all(|(a,b)| a == b) {
                     break;
@@ -975,12 +1428,84 @@ trait RocksTable: Debug + Send + Sync + Clone {
                 || !value.iter().zip(secondary_key_val).all(|(a,b)| a == b) {
                     continue;
                 }
-                res.push(row_id);
+                if !f(row_id)? {
+                    break;
+                }
             };
         };
+        Ok(())
+    }
+
+    fn get_row_from_ordered_index(&self, secondary_id: u32, from_val: &Vec<u8>, to_val: &Vec<u8>, expect_unique: bool) -> Result<Vec<u64>, CubeError> {
+        let ref db = self.db();
+        let index_id = self.index_id(secondary_id);
+        let key_min = RowKey::SecondaryIndex(index_id, from_val.clone(), 0);
+
+        let mut res: Vec<u64> = Vec::with_capacity(if expect_unique { 1 } else { 16 });
+        // Ordered keys aren't bound to the fixed prefix so the prefix extractor must not be used for this seek
+        let mut opts = ReadOptions::default();
+        opts.set_total_order_seek(true);
+        let mut iter = db.raw_iterator_opt(opts);
+        iter.seek(&key_min.to_bytes()[0..(from_val.len()+5)]);
+
+        while iter.valid() {
+            if let (Some(key), Some(value)) = (iter.key(), iter.value()) {
+                match RowKey::from_bytes(key)? {
+                    RowKey::SecondaryIndex(id, _, row_id) if id == index_id => {
+                        if value > to_val.as_slice() {
+                            break;
+                        }
+                        if value >= from_val.as_slice() {
+                            res.push(row_id);
+                            if expect_unique {
+                                break;
+                            }
+                        }
+                    },
+                    _ => break
+                }
+            }
+            iter.next();
+        }
         Ok(res)
     }
 
+    fn get_row_ids_by_index_val(&self, secondary_id: u32, index_val: &Vec<u8>, key_prefix: &Vec<u8>, is_ordered: bool, expect_unique: bool) -> Result<Vec<u64>, CubeError> {
+        if is_ordered {
+            self.get_row_from_ordered_index(secondary_id, index_val, index_val, expect_unique)
+        } else {
+            self.get_row_from_index(secondary_id, index_val, key_prefix, expect_unique)
+        }
+    }
+
+    // Adds missing entries of an index introduced after rows were written. Returns the number of rows reindexed.
+    fn backfill_index(&self, index: &impl BaseRocksSecondaryIndex<Self::T>, batch_pipe: &mut BatchPipe) -> Result<usize, CubeError> {
+        let db = self.db();
+        let mut reindexed = 0;
+        for row in self.all_rows()? {
+            let key = RowKey::SecondaryIndex(self.index_id(index.get_id()), index.key_prefix(row.get_row()), row.get_id()).to_bytes();
+            if db.get_pinned(&key)?.is_none() {
+                batch_pipe.batch().put(key, index.index_key_by(row.get_row()));
+                reindexed += 1;
+            }
+        }
+        Ok(reindexed)
+    }
+
+    fn check_index_integrity(&self) -> Result<Vec<String>, CubeError> {
+        let mut discrepancies = Vec::new();
+        for row in self.all_rows()? {
+            for index in Self::indexes().iter() {
+                let index_val = index.index_key_by(row.get_row());
+                let row_ids = self.get_row_ids_by_index_val(index.get_id(), &index_val, &index.key_prefix(row.get_row()), index.is_ordered(), false)?;
+                if !row_ids.contains(&row.get_id()) {
+                    discrepancies.push(format!("Row {} of {:?} is missing in {:?} index", row.get_id(), self, index));
+                }
+            }
+        }
+        Ok(discrepancies)
+    }
+
     fn all_rows(&self) -> Result<Vec<IdRow<Self::T>>, CubeError> {
         let mut res = Vec::new();
         let db = self.db();
@@ -990,11 +1515,28 @@ trait RocksTable: Debug + Send + Sync + Clone {
         Ok(res)
     }
 
+    // Corrupt rows are logged and skipped so the rest of the table is still readable
+    fn all_rows_lenient(&self) -> Result<Vec<IdRow<Self::T>>, CubeError> {
+        let mut res = Vec::new();
+        let db = self.db();
+        for row in self.table_scan(&db)? {
+            match row {
+                Ok(row) => res.push(row),
+                Err(e) => error!("Skipping corrupt row of {:?}: {}", self.table_id(), e)
+            }
+        }
+        Ok(res)
+    }
+
+    // Yields an error item per corrupt row and keeps going so callers can choose to skip past it
     fn table_scan<'a>(&'a self, db: &'a DB) -> Result<TableScanIter<'a, Self>, CubeError> {
         let my_table_id = self.table_id();
         let key_min = RowKey::Table(my_table_id, 0);
 
-        let iterator = db.prefix_iterator::<'a, 'a>(&key_min.to_bytes()[0..get_fixed_prefix()]);
+        let mut opts = ReadOptions::default();
+        opts.set_prefix_same_as_start(true);
+        let mut iterator = db.raw_iterator_opt(opts);
+        iterator.seek(&key_min.to_bytes()[0..get_fixed_prefix()]);
 
         Ok(TableScanIter {
             table_id: my_table_id,
@@ -1003,6 +1545,19 @@ trait RocksTable: Debug + Send + Sync + Clone {
         })
     }
 
+    fn snapshot_rows(&self, snapshot: &Snapshot) -> Result<Vec<IdRow<Self::T>>, CubeError> {
+        let mut opts = ReadOptions::default();
+        opts.set_prefix_same_as_start(true);
+        let mut iterator = snapshot.raw_iterator_opt(opts);
+        iterator.seek(&RowKey::Table(self.table_id(), 0).to_bytes()[0..get_fixed_prefix()]);
+
+        TableScanIter {
+            table_id: self.table_id(),
+            iter: iterator,
+            table: self
+        }.collect::<Result<Vec<_>, _>>()
+    }
+
     fn build_path_rows<C: Clone, P>(
         &self,
         children: Vec<IdRow<C>>,
@@ -1024,6 +1579,34 @@ pub enum WriteBatchEntry {
     Delete { key: Box<[u8]> }
 }
 
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+// Partitions without min value go first as it stands for minus infinity
+fn sort_partitions_by_min_value(partitions: &mut Vec<IdRow<Partition>>, sort_key_size: u64) {
+    partitions.sort_by(|a, b| match (a.get_row().get_min_val(), b.get_row().get_min_val()) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Less,
+        (Some(_), None) => Ordering::Greater,
+        (Some(a), Some(b)) => a.sort_key(sort_key_size).cmp(&b.sort_key(sort_key_size))
+    });
+}
+
+fn compress(data: &[u8]) -> Result<Vec<u8>, CubeError> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?)
+}
+
+// Files written before compression was introduced are stored raw so they're passed through as is
+fn decompress_if_needed(data: Vec<u8>) -> Result<Vec<u8>, CubeError> {
+    if !data.starts_with(&GZIP_MAGIC) {
+        return Ok(data);
+    }
+    let mut decompressed = Vec::new();
+    GzDecoder::new(data.as_slice()).read_to_end(&mut decompressed)?;
+    Ok(decompressed)
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug)]
 struct WriteBatchContainer {
     entries: Vec<WriteBatchEntry>
@@ -1034,22 +1617,12 @@ impl WriteBatchContainer {
         Self { entries: Vec::new() }
     }
 
-    fn write_batch(&self) -> WriteBatch {
-        let mut batch = WriteBatch::default();
-        for entry in self.entries.iter() {
-            match entry {
-                WriteBatchEntry::Put { key, value } => batch.put(key, value),
-                WriteBatchEntry::Delete { key } => batch.delete(key)
-            }
-        }
-        batch
-    }
-
     async fn write_to_file(&self, file_name: &str) -> Result<(), CubeError> {
         let mut ser = flexbuffers::FlexbufferSerializer::new();
         self.serialize(&mut ser)?;
+        let compressed = compress(ser.view())?;
         let mut file = File::create(file_name).await?;
-        Ok(tokio::io::AsyncWriteExt::write_all(&mut file, ser.view()).await?)
+        Ok(tokio::io::AsyncWriteExt::write_all(&mut file, &compressed).await?)
     }
 
     async fn read_from_file(file_name: &str) -> Result<Self, CubeError> {
@@ -1057,8 +1630,41 @@ impl WriteBatchContainer {
 
         let mut buffer = Vec::new();
         tokio::io::AsyncReadExt::read_to_end(&mut file, &mut buffer).await?;
-        let r = flexbuffers::Reader::get_root(&buffer).unwrap();
-        Ok(Self::deserialize(r)?)
+        let buffer = decompress_if_needed(buffer)?;
+        let r = flexbuffers::Reader::get_root(&buffer)
+            .map_err(|e| CubeError::internal(format!("Can't read write batch log {}: {:?}", file_name, e)))?;
+        Ok(WriteBatchContainer::deserialize(r)?)
+    }
+
+    // Decodes entries one by one straight from the file buffer and writes them in bounded batches
+    // so replaying a huge log doesn't materialize the whole container and its write batch at once
+    async fn apply_from_file(file_name: &str, db: &DB, max_batch_entries: usize) -> Result<usize, CubeError> {
+        let mut file = File::open(file_name).await?;
+
+        let mut buffer = Vec::new();
+        tokio::io::AsyncReadExt::read_to_end(&mut file, &mut buffer).await?;
+        let buffer = decompress_if_needed(buffer)?;
+        let r = flexbuffers::Reader::get_root(&buffer)
+            .map_err(|e| CubeError::internal(format!("Can't read write batch log {}: {:?}", file_name, e)))?;
+        let entries = r.as_map().idx("entries").as_vector();
+
+        let mut batch = WriteBatch::default();
+        let mut applied = 0;
+        for entry in entries.iter() {
+            match WriteBatchEntry::deserialize(entry)? {
+                WriteBatchEntry::Put { key, value } => batch.put(key, value),
+                WriteBatchEntry::Delete { key } => batch.delete(key)
+            }
+            applied += 1;
+            if batch.len() >= max_batch_entries {
+                db.write(batch)?;
+                batch = WriteBatch::default();
+            }
+        }
+        if !batch.is_empty() {
+            db.write(batch)?;
+        }
+        Ok(applied)
     }
 }
 
@@ -1072,55 +1678,230 @@ impl WriteBatchIterator for WriteBatchContainer {
     }
 }
 
+#[derive(Debug)]
+struct CatalogCache {
+    schemas: HashMap<String, IdRow<Schema>>,
+    tables: Vec<IdRow<Table>>,
+    tables_by_name: HashMap<(u64, String), IdRow<Table>>,
+    indexes_by_table: HashMap<u64, Vec<IdRow<Index>>>
+}
+
+impl CatalogCache {
+    fn load(db: Arc<DB>) -> Result<Self, CubeError> {
+        let schemas = SchemaRocksTable::new(db.clone()).all_rows()?;
+        let tables = TableRocksTable::new(db.clone()).all_rows()?;
+        let indexes = IndexRocksTable::new(db).all_rows()?;
+        let mut indexes_by_table: HashMap<u64, Vec<IdRow<Index>>> = HashMap::new();
+        for index in indexes.into_iter() {
+            indexes_by_table.entry(index.get_row().table_id()).or_insert_with(Vec::new).push(index);
+        }
+        Ok(CatalogCache {
+            schemas: schemas.into_iter().map(|s| (s.get_row().get_name().to_string(), s)).collect(),
+            tables_by_name: tables.iter().map(|t| ((t.get_row().get_schema_id(), t.get_row().get_table_name().to_string()), t.clone())).collect(),
+            tables,
+            indexes_by_table
+        })
+    }
+
+    fn is_affected_by(event: &MetaStoreEvent) -> bool {
+        match event {
+            MetaStoreEvent::Insert(table_id, _) |
+            MetaStoreEvent::Update(table_id, _) |
+            MetaStoreEvent::Delete(table_id, _) |
+            MetaStoreEvent::DeleteCoalesced(table_id, _) => match table_id {
+                TableId::Schemas | TableId::Tables | TableId::Indexes => true,
+                _ => false
+            },
+            MetaStoreEvent::DeleteSchema(_) |
+            MetaStoreEvent::SchemaRenamed { .. } |
+            MetaStoreEvent::DeleteTable(_) |
+            MetaStoreEvent::DeleteIndex(_) => true,
+            _ => false
+        }
+    }
+}
+
 impl RocksMetaStore {
-    pub fn with_listener(path: impl AsRef<Path>, listeners: Vec<Sender<MetaStoreEvent>>, remote_fs: Arc<dyn RemoteFs>) -> Arc<RocksMetaStore> {
-        let meta_store = RocksMetaStore::with_listener_impl(path, listeners, remote_fs);
-        Arc::new(meta_store)
+    pub fn with_listener(path: impl AsRef<Path>, listeners: Vec<Sender<MetaStoreEvent>>, remote_fs: Arc<dyn RemoteFs>, config: MetaStoreConfig) -> Result<Arc<RocksMetaStore>, CubeError> {
+        let meta_store = RocksMetaStore::with_listener_impl(path, listeners, remote_fs, config)?;
+        Ok(Arc::new(meta_store))
+    }
+
+    pub fn with_listener_impl(path: impl AsRef<Path>, listeners: Vec<Sender<MetaStoreEvent>>, remote_fs: Arc<dyn RemoteFs>, config: MetaStoreConfig) -> Result<RocksMetaStore, CubeError> {
+        RocksMetaStore::open(path, listeners, remote_fs, config, false)
+    }
+
+    fn open(path: impl AsRef<Path>, listeners: Vec<Sender<MetaStoreEvent>>, remote_fs: Arc<dyn RemoteFs>, config: MetaStoreConfig, follower: bool) -> Result<RocksMetaStore, CubeError> {
+        let db = RocksMetaStore::open_db(path)?;
+        let meta_store = RocksMetaStore::from_db(db.clone(), listeners, remote_fs, config, follower)?;
+        // Runs after last_upload_seq is taken so backfilled entries go out with the next log upload
+        Self::backfill_indexes(&db, !follower)?;
+        Ok(meta_store)
     }
 
-    pub fn with_listener_impl(path: impl AsRef<Path>, listeners: Vec<Sender<MetaStoreEvent>>, remote_fs: Arc<dyn RemoteFs>) -> RocksMetaStore {
+    fn open_db(path: impl AsRef<Path>) -> Result<Arc<DB>, CubeError> {
         let mut opts = Options::default();
         opts.create_if_missing(true);
         opts.set_prefix_extractor(rocksdb::SliceTransform::create_fixed_prefix(13));
 
-        let db = DB::open(&opts, path).unwrap();
-        let db_arc = Arc::new(db);
+        // RocksDB holds a LOCK file for the whole lifetime of the DB so a second open of the same path fails here
+        let db = DB::open(&opts, path.as_ref()).map_err(|e| CubeError::internal(format!(
+            "Can't open metastore at {}: {}", path.as_ref().to_string_lossy(), e
+        )))?;
+        Ok(Arc::new(db))
+    }
 
-        let meta_store = RocksMetaStore {
+    fn from_db(db_arc: Arc<DB>, listeners: Vec<Sender<MetaStoreEvent>>, remote_fs: Arc<dyn RemoteFs>, config: MetaStoreConfig, follower: bool) -> Result<RocksMetaStore, CubeError> {
+        if config.max_concurrent_writes == Some(0) {
+            return Err(CubeError::user("Metastore max concurrent writes can't be 0 as no write would ever run".to_string()));
+        }
+        let write_semaphore = config.max_concurrent_writes.map(|permits| Arc::new(Semaphore::new(permits)));
+        let last_checkpoint_time = config.clock.now();
+        Ok(RocksMetaStore {
             db: Arc::new(RwLock::new(db_arc.clone())),
+            config,
+            listener_dropped_counts: Arc::new(RwLock::new(vec![0; listeners.len()])),
             listeners: Arc::new(RwLock::new(listeners)),
             remote_fs,
-            last_checkpoint_time: Arc::new(RwLock::new(SystemTime::now())),
+            last_checkpoint_time: Arc::new(RwLock::new(last_checkpoint_time)),
             write_notify: Arc::new(Notify::new()),
             write_completed_notify: Arc::new(Notify::new()),
             last_upload_seq: Arc::new(RwLock::new(db_arc.latest_sequence_number())),
             last_check_seq: Arc::new(RwLock::new(db_arc.latest_sequence_number())),
-            upload_loop_enabled: Arc::new(RwLock::new(true))
+            last_applied_log_seq: Arc::new(RwLock::new(0)),
+            upload_loop_enabled: Arc::new(RwLock::new(true)),
+            follower_loop_enabled: Arc::new(RwLock::new(true)),
+            followed_checkpoint: Arc::new(RwLock::new(None)),
+            cache_full_catalog: Arc::new(RwLock::new(false)),
+            catalog_cache: Arc::new(RwLock::new((0, None))),
+            write_semaphore,
+            writes_since_checkpoint: Arc::new(RwLock::new(0)),
+            uncompacted_logs: Arc::new(RwLock::new(Vec::new())),
+            pending_log_deletions: Arc::new(RwLock::new(Vec::new())),
+            follower
+        })
+    }
+
+    // Metastores written before an index was added have no entries for it.
+    // Applied migrations are recorded under the migration version key so each one scans the data only once.
+    // Followers don't record them: the primary's marker arrives with its logs and older primaries keep writing rows without the entries.
+    fn backfill_indexes(db: &Arc<DB>, mark_done: bool) -> Result<(), CubeError> {
+        let version = Self::migration_version(db)?;
+        if version >= METASTORE_MIGRATION_VERSION {
+            return Ok(());
+        }
+        let mut batch_pipe = BatchPipe::new(db.as_ref());
+        let partitions_reindexed = if version < 1 {
+            PartitionRocksTable::new(db.clone()).backfill_index(&PartitionRocksIndex::MinValue, &mut batch_pipe)?
+        } else {
+            0
+        };
+        let tables_reindexed = if version < 2 {
+            TableRocksTable::new(db.clone()).backfill_index(&TableRocksIndex::SchemaId, &mut batch_pipe)?
+        } else {
+            0
         };
-        meta_store
+        if tables_reindexed + partitions_reindexed > 0 {
+            info!(
+                "Backfilled {} table schema index entries and {} partition min value index entries",
+                tables_reindexed,
+                partitions_reindexed
+            );
+        }
+        if mark_done {
+            batch_pipe.batch().put(migration_version_key(), METASTORE_MIGRATION_VERSION.to_be_bytes());
+        }
+        if !batch_pipe.batch().is_empty() {
+            batch_pipe.batch_write_rows()?;
+        }
+        Ok(())
     }
 
-    pub fn new(path: impl AsRef<Path>, remote_fs: Arc<dyn RemoteFs>) -> Arc<RocksMetaStore> {
-        Self::with_listener(path, vec![], remote_fs)
+    fn migration_version(db: &DB) -> Result<u64, CubeError> {
+        match db.get_pinned(migration_version_key())? {
+            Some(value) => Cursor::new(value.as_ref()).read_u64::<BigEndian>().map_err(
+                |e| CubeError::corruption(format!("Can't decode metastore migration version {:?}: {}", value.as_ref(), e))
+            ),
+            None => Ok(0)
+        }
     }
 
-    pub async fn load_from_remote(path: impl AsRef<Path>, remote_fs: Arc<dyn RemoteFs>) -> Result<Arc<RocksMetaStore>, CubeError> {
-        if !fs::metadata(path.as_ref()).await.is_ok() {
-            let re = Regex::new(r"^metastore-(\d+)").unwrap();
+    pub fn new(path: impl AsRef<Path>, remote_fs: Arc<dyn RemoteFs>) -> Result<Arc<RocksMetaStore>, CubeError> {
+        Self::with_listener(path, vec![], remote_fs, MetaStoreConfig::default())
+    }
 
-            if remote_fs.list("metastore-current").await?.iter().len() > 0 {
-                info!("Downloading remote metastore");
-                let current_metastore_file = remote_fs.local_file("metastore-current").await?;
-                if fs::metadata(current_metastore_file.as_str()).await.is_ok() {
-                    fs::remove_file(current_metastore_file.as_str()).await?;
+    pub async fn load_from_remote(path: impl AsRef<Path>, remote_fs: Arc<dyn RemoteFs>, config: MetaStoreConfig, verify_on_start: bool) -> Result<Arc<RocksMetaStore>, CubeError> {
+        let (meta_store, _) = Self::load_from_remote_impl(path, remote_fs, config, false).await?;
+        if verify_on_start {
+            info!("Verifying metastore integrity");
+            let discrepancies = meta_store.check_integrity().await?;
+            if !discrepancies.is_empty() {
+                for d in discrepancies.iter() {
+                    error!("Metastore integrity check failed: {}", d);
                 }
-                remote_fs.download_file("metastore-current").await?;
+                return Err(CubeError::internal(format!(
+                    "Metastore integrity check found {} discrepancies: {}",
+                    discrepancies.len(),
+                    discrepancies.join("; ")
+                )));
+            }
+        }
+        Ok(meta_store)
+    }
+
+    /// Opens a read replica that follows the checkpoint and logs uploaded by the primary, see `run_follower_loop`.
+    /// The followed checkpoint and the last applied log are kept next to the local metastore to resume after restart.
+    pub async fn load_follower(path: impl AsRef<Path>, remote_fs: Arc<dyn RemoteFs>, config: MetaStoreConfig) -> Result<Arc<RocksMetaStore>, CubeError> {
+        let state_path = path.as_ref().with_extension("follower");
+        let (meta_store, loaded_checkpoint) = Self::load_from_remote_impl(path, remote_fs.clone(), config, true).await?;
+        if let Some(loaded_checkpoint) = loaded_checkpoint {
+            *meta_store.followed_checkpoint.write().await = Some(loaded_checkpoint);
+        } else if fs::metadata(&state_path).await.is_ok() {
+            let state = fs::read_to_string(&state_path).await?;
+            let mut lines = state.lines();
+            let (checkpoint, seq) = match (lines.next(), lines.next().map(|seq| u64::from_str(seq))) {
+                (Some(checkpoint), Some(Ok(seq))) => (checkpoint.to_string(), seq),
+                _ => return Err(CubeError::corruption(format!(
+                    "Can't parse metastore follower state in {}: '{}'", state_path.to_string_lossy(), state
+                )))
+            };
+            *meta_store.followed_checkpoint.write().await = Some(checkpoint);
+            *meta_store.last_applied_log_seq.write().await = seq;
+        } else {
+            // Local metastore without follower state: replay all logs of the current checkpoint on top of it
+            *meta_store.followed_checkpoint.write().await = Self::read_remote_current(remote_fs.as_ref()).await?;
+        }
+        meta_store.save_follower_state().await?;
+        Ok(meta_store)
+    }
+
+    // Always downloads a fresh copy as the primary rewrites it on every checkpoint
+    async fn read_remote_current(remote_fs: &dyn RemoteFs) -> Result<Option<String>, CubeError> {
+        if remote_fs.list("metastore-current").await?.is_empty() {
+            return Ok(None);
+        }
+        let current_metastore_file = remote_fs.local_file("metastore-current").await?;
+        if fs::metadata(current_metastore_file.as_str()).await.is_ok() {
+            fs::remove_file(current_metastore_file.as_str()).await?;
+        }
+        remote_fs.download_file("metastore-current").await?;
+        Self::check_downloaded_file_not_empty("metastore-current", &current_metastore_file).await?;
+
+        let mut file = File::open(current_metastore_file.as_str()).await?;
+        let mut buffer = Vec::new();
+        tokio::io::AsyncReadExt::read_to_end(&mut file, &mut buffer).await?;
+        Ok(Some(String::from_utf8(buffer)?))
+    }
 
-                let mut file = File::open(current_metastore_file.as_str()).await?;
-                let mut buffer = Vec::new();
-                tokio::io::AsyncReadExt::read_to_end(&mut file, &mut buffer).await?;
+    // Also returns the remote checkpoint the metastore was downloaded from if any
+    async fn load_from_remote_impl(path: impl AsRef<Path>, remote_fs: Arc<dyn RemoteFs>, config: MetaStoreConfig, follower: bool) -> Result<(Arc<RocksMetaStore>, Option<String>), CubeError> {
+        if !fs::metadata(path.as_ref()).await.is_ok() {
+            let re = Regex::new(r"^metastore-(\d+)").unwrap();
+
+            if let Some(current) = Self::read_remote_current(remote_fs.as_ref()).await? {
+                info!("Downloading remote metastore");
                 let last_metastore_snapshot = {
-                    let parse_result = re.captures(&String::from_utf8(buffer)?)
+                    let parse_result = re.captures(&current)
                         .map(|c| c.get(1).unwrap().as_str())
                         .map(|p| u128::from_str(p));
                     if let Some(Ok(millis)) = parse_result {
@@ -1131,27 +1912,46 @@ impl RocksMetaStore {
                 };
 
                 if let Some(snapshot) = last_metastore_snapshot {
-                    let to_load = remote_fs.list(&format!("metastore-{}", snapshot)).await?;
+                    let to_load = remote_fs.list(&format!("metastore-{}/", snapshot)).await?;
                     let meta_store_path = remote_fs.local_file("metastore").await?;
                     fs::create_dir_all(meta_store_path.to_string()).await?;
                     for file in to_load.iter() {
                         remote_fs.download_file(file).await?;
                         let local = remote_fs.local_file(file).await?;
                         let path = Path::new(&local);
-                        fs::copy(path, PathBuf::from(&meta_store_path).join(path.file_name().unwrap().to_str().unwrap())).await?;
+                        let file_name = path.file_name().unwrap().to_str().unwrap();
+                        // Uncompressed RocksDB WAL files are legitimately empty when there were no writes since the last flush
+                        if !file_name.ends_with(".log") {
+                            Self::check_downloaded_file_not_empty(file, &local).await?;
+                        }
+                        if let Some(original_name) = file_name.strip_suffix(".gz") {
+                            let source = path.to_path_buf();
+                            let dest = PathBuf::from(&meta_store_path).join(original_name);
+                            tokio::task::spawn_blocking(move || -> Result<(), CubeError> {
+                                let mut decoder = GzDecoder::new(std::fs::File::open(source)?);
+                                std::io::copy(&mut decoder, &mut std::fs::File::create(dest)?)?;
+                                Ok(())
+                            }).await??;
+                        } else {
+                            fs::copy(path, PathBuf::from(&meta_store_path).join(file_name)).await?;
+                        }
                     }
 
-                    let meta_store = Self::new(path.as_ref(), remote_fs.clone());
+                    // Migrations wait for the logs as the checkpoint alone doesn't hold all of the data
+                    let meta_store = Arc::new(Self::from_db(Self::open_db(path.as_ref())?, vec![], remote_fs.clone(), config, follower)?);
 
-                    let logs_to_batch = remote_fs.list(&format!("metastore-{}-logs", snapshot)).await?;
-                    for log_file in logs_to_batch.iter() {
-                        let path_to_log = remote_fs.local_file(log_file).await?;
-                        let batch = WriteBatchContainer::read_from_file(&path_to_log).await?;
+                    let logs_to_batch = Self::list_remote_logs(remote_fs.as_ref(), &format!("metastore-{}-logs", snapshot)).await?;
+                    for (seq, log_file) in logs_to_batch.iter() {
+                        let path_to_log = remote_fs.download_file(log_file).await?;
+                        Self::check_downloaded_file_not_empty(log_file, &path_to_log).await?;
                         let db = meta_store.db.write().await;
-                        db.write(batch.write_batch())?;
+                        WriteBatchContainer::apply_from_file(&path_to_log, &db, 10000).await?; // TODO config
+                        *meta_store.last_applied_log_seq.write().await = *seq;
                     }
+                    // Logs may come from a primary that didn't write newer indexes yet
+                    Self::backfill_indexes(&*meta_store.db.read().await, !follower)?;
 
-                    return Ok(meta_store);
+                    return Ok((meta_store, Some(format!("metastore-{}", snapshot))));
                 }
             }
             info!("Creating metastore from scratch in {}", path.as_ref().as_os_str().to_string_lossy());
@@ -1159,11 +1959,194 @@ impl RocksMetaStore {
             info!("Using existing metastore in {}", path.as_ref().as_os_str().to_string_lossy());
         }
 
-        Ok(Self::new(path, remote_fs))
+        Ok((Arc::new(Self::open(path, vec![], remote_fs, config, follower)?), None))
+    }
+
+    async fn check_downloaded_file_not_empty(remote_path: &str, local_path: &str) -> Result<(), CubeError> {
+        if fs::metadata(local_path).await?.len() == 0 {
+            return Err(CubeError::corruption(format!(
+                "Downloaded metastore file {} is empty: remote object is likely truncated", remote_path
+            )));
+        }
+        Ok(())
+    }
+
+    /// Deletes secondary index entries whose row is gone, e.g. after a partially applied write. Returns the count removed.
+    pub async fn vacuum_orphan_index_entries(&self) -> Result<usize, CubeError> {
+        // Scanning in a snapshot doesn't block writers; candidates are checked again before deleting
+        let candidates = self.read_operation(|db_ref| -> Result<Vec<(Vec<u8>, TableId, u64)>, CubeError> {
+            let snapshot = db_ref.snapshot();
+            let mut opts = ReadOptions::default();
+            opts.set_total_order_seek(true);
+            let mut iter = snapshot.raw_iterator_opt(opts);
+            iter.seek(&[3]);
+            let mut candidates = Vec::new();
+            while let Some(key) = iter.key() {
+                match RowKey::from_bytes(key)? {
+                    RowKey::SecondaryIndex(index_id, _, row_id) => {
+                        let table_id = TableId::from_index_id(index_id).ok_or_else(
+                            || CubeError::corruption(format!("Index {} doesn't belong to any table", index_id))
+                        )?;
+                        if snapshot.get(RowKey::Table(table_id, row_id).to_bytes())?.is_none() {
+                            candidates.push((key.to_vec(), table_id, row_id));
+                        }
+                    }
+                    _ => break
+                }
+                iter.next();
+            }
+            Ok(candidates)
+        }).await?;
+        if candidates.is_empty() {
+            return Ok(0);
+        }
+
+        self.write_operation(move |db_ref, batch_pipe| {
+            let mut removed = 0;
+            for (key, table_id, row_id) in candidates.iter() {
+                // Row could be inserted after the snapshot was taken
+                if db_ref.get_pinned(RowKey::Table(*table_id, *row_id).to_bytes())?.is_none() {
+                    batch_pipe.batch().delete(key);
+                    removed += 1;
+                }
+            }
+            if removed > 0 {
+                warn!("Removing {} orphaned secondary index entries", removed);
+            }
+            Ok(removed)
+        }).await
+    }
+
+    pub async fn check_integrity(&self) -> Result<Vec<String>, CubeError> {
+        self.read_operation(|db_ref| {
+            let schemas = SchemaRocksTable::new(db_ref.clone());
+            let tables = TableRocksTable::new(db_ref.clone());
+            let indexes = IndexRocksTable::new(db_ref.clone());
+            let partitions = PartitionRocksTable::new(db_ref.clone());
+            let chunks = ChunkRocksTable::new(db_ref.clone());
+            let wals = WALRocksTable::new(db_ref.clone());
+            let jobs = JobRocksTable::new(db_ref);
+
+            let mut res = Vec::new();
+            res.extend(schemas.check_index_integrity()?);
+            res.extend(tables.check_index_integrity()?);
+            res.extend(indexes.check_index_integrity()?);
+            res.extend(partitions.check_index_integrity()?);
+            res.extend(chunks.check_index_integrity()?);
+            res.extend(wals.check_index_integrity()?);
+            res.extend(jobs.check_index_integrity()?);
+
+            for table in tables.all_rows()? {
+                if !schemas.exists(table.get_row().get_schema_id())? {
+                    res.push(format!("Table {} references missing schema {}", table.get_id(), table.get_row().get_schema_id()));
+                }
+            }
+            for index in indexes.all_rows()? {
+                if !tables.exists(index.get_row().table_id())? {
+                    res.push(format!("Index {} references missing table {}", index.get_id(), index.get_row().table_id()));
+                }
+            }
+            for partition in partitions.all_rows()? {
+                if !indexes.exists(partition.get_row().get_index_id())? {
+                    res.push(format!("Partition {} references missing index {}", partition.get_id(), partition.get_row().get_index_id()));
+                }
+            }
+            for chunk in chunks.all_rows()? {
+                if !partitions.exists(chunk.get_row().get_partition_id())? {
+                    res.push(format!("Chunk {} references missing partition {}", chunk.get_id(), chunk.get_row().get_partition_id()));
+                }
+            }
+            for wal in wals.all_rows()? {
+                if !tables.exists(wal.get_row().get_table_id())? {
+                    res.push(format!("WAL {} references missing table {}", wal.get_id(), wal.get_row().get_table_id()));
+                }
+            }
+            Ok(res)
+        }).await
+    }
+
+    pub async fn listener_dropped_counts(&self) -> Vec<u64> {
+        self.listener_dropped_counts.read().await.clone()
     }
 
     pub async fn add_listener(&self, listener: Sender<MetaStoreEvent>) {
-        self.listeners.write().await.push(listener);
+        // Listeners are locked first, the same order subscribe_with_snapshot uses, and the counter is pushed
+        // under that lock so it always exists for every listener visible to write_operation
+        let mut listeners = self.listeners.write().await;
+        self.listener_dropped_counts.write().await.push(0);
+        listeners.push(listener);
+    }
+
+    /// Registers a listener and replays every existing row to it as `MetaStoreEvent::Insert` first.
+    /// Writes aren't blocked by the snapshot: events of writes landing meanwhile are staged and forwarded
+    /// after the snapshot, so snapshot events precede every live event. A row inserted concurrently is
+    /// reported once, while an update or delete racing with the snapshot may be reported on top of it.
+    pub async fn subscribe_with_snapshot(&self, listener: Sender<MetaStoreEvent>) -> Result<(), CubeError> {
+        let (staging, mut staged) = broadcast::channel(10000); // TODO config
+        let listener_index = {
+            let mut listeners = self.listeners.write().await;
+            self.listener_dropped_counts.write().await.push(0);
+            listeners.push(staging);
+            listeners.len() - 1
+        };
+
+        let row_keys = self.read_operation(|db_ref| -> Result<Vec<(TableId, u64)>, CubeError> {
+            let mut row_keys = Vec::new();
+            for table_id in ALL_TABLE_IDS.iter() {
+                let mut opts = ReadOptions::default();
+                opts.set_prefix_same_as_start(true);
+                let mut iterator = db_ref.raw_iterator_opt(opts);
+                iterator.seek(&RowKey::Table(*table_id, 0).to_bytes()[0..get_fixed_prefix()]);
+                while let Some(key) = iterator.key() {
+                    if let RowKey::Table(table_id, row_id) = RowKey::from_bytes(key)? {
+                        row_keys.push((table_id, row_id));
+                    }
+                    iterator.next();
+                }
+            }
+            Ok(row_keys)
+        }).await?;
+
+        let mut dropped = 0;
+        for (table_id, row_id) in row_keys.iter() {
+            if listener.send(MetaStoreEvent::Insert(*table_id, *row_id)).is_err() {
+                dropped += 1;
+            }
+        }
+        // Writes committed before the scan can still be dispatched to the staging channel
+        let snapshot_keys = row_keys.into_iter().collect::<HashSet<_>>();
+        dropped += Self::forward_staged_events(&mut staged, &listener, &snapshot_keys);
+        {
+            // Dispatch happens under the read lock so nothing is sent to the staging channel after this
+            let mut listeners = self.listeners.write().await;
+            dropped += Self::forward_staged_events(&mut staged, &listener, &snapshot_keys);
+            listeners[listener_index] = listener;
+        }
+        if dropped > 0 {
+            warn!("Metastore listener {} dropped {} snapshot events", listener_index, dropped);
+            self.listener_dropped_counts.write().await[listener_index] += dropped;
+        }
+        Ok(())
+    }
+
+    fn forward_staged_events(
+        staged: &mut broadcast::Receiver<MetaStoreEvent>,
+        listener: &Sender<MetaStoreEvent>,
+        snapshot_keys: &HashSet<(TableId, u64)>
+    ) -> u64 {
+        let mut dropped = 0;
+        loop {
+            match staged.try_recv() {
+                Ok(MetaStoreEvent::Insert(table_id, row_id)) if snapshot_keys.contains(&(table_id, row_id)) => {}
+                Ok(event) => {
+                    if listener.send(event).is_err() {
+                        dropped += 1;
+                    }
+                }
+                Err(broadcast::TryRecvError::Lagged(n)) => dropped += n,
+                Err(broadcast::TryRecvError::Empty) | Err(broadcast::TryRecvError::Closed) => return dropped
+            }
+        }
     }
 
     async fn write_operation<F, R>(&self, f: F) -> Result<R, CubeError>
@@ -1171,29 +2154,102 @@ impl RocksMetaStore {
             F: FnOnce(Arc<DB>, &mut BatchPipe) -> Result<R, CubeError> + Send + 'static,
             R: Send + 'static,
     {
+        self.check_not_follower()?;
+        let permit = match self.write_semaphore.as_ref() {
+            Some(semaphore) => Some(semaphore.acquire().await),
+            None => None
+        };
         let db = self.db.write().await.clone();
         let (spawn_res, events) = tokio::task::spawn_blocking(move || -> Result<(R, Vec<MetaStoreEvent>), CubeError> {
             let mut batch = BatchPipe::new(db.as_ref());
             let res = f(db.clone(), &mut batch)?;
             let write_result = batch.batch_write_rows()?;
             Ok((res, write_result))
-        }).await??;
+        }).await.map_err(|e| {
+            // Nothing is written as the batch is dropped along with the task and tokio locks aren't poisoned
+            error!("Metastore write task failed: {}", e);
+            if e.is_panic() {
+                CubeError::internal("metastore write task panicked".to_string())
+            } else {
+                CubeError::internal("metastore write task was cancelled".to_string())
+            }
+        })??;
+        drop(permit);
 
+        *self.writes_since_checkpoint.write().await += 1;
         self.write_notify.notify();
 
-        for listener in self.listeners.read().await.clone().iter_mut() {
-            for event in events.iter() {
-                listener.send(event.clone())?;
+        if events.iter().any(|e| CatalogCache::is_affected_by(e)) {
+            let mut catalog_cache = self.catalog_cache.write().await;
+            *catalog_cache = (catalog_cache.0 + 1, None);
+        }
+
+        // Sending doesn't block so the lock is only held while dispatching, which lets
+        // subscribe_with_snapshot swap its staging channel without losing events
+        let dropped = {
+            let listeners = self.listeners.read().await;
+            let mut dropped = vec![0; listeners.len()];
+            for (i, listener) in listeners.iter().enumerate() {
+                for event in events.iter() {
+                    if listener.send(event.clone()).is_err() {
+                        dropped[i] += 1;
+                    }
+                }
+            }
+            dropped
+        };
+
+        if dropped.iter().any(|d| *d > 0) {
+            let mut counts = self.listener_dropped_counts.write().await;
+            for (i, d) in dropped.into_iter().enumerate().filter(|(_, d)| *d > 0) {
+                counts[i] += d;
+                warn!("Metastore listener {} dropped {} events ({} in total)", i, d, counts[i]);
             }
         }
 
         Ok(spawn_res)
     }
 
-    pub async fn run_upload_loop(&self) {
+    pub async fn set_cache_full_catalog(&self, enabled: bool) {
+        *self.cache_full_catalog.write().await = enabled;
+        if !enabled {
+            let mut catalog_cache = self.catalog_cache.write().await;
+            *catalog_cache = (catalog_cache.0 + 1, None);
+        }
+    }
+
+    async fn cached_catalog(&self) -> Result<Option<Arc<CatalogCache>>, CubeError> {
+        if !*self.cache_full_catalog.read().await {
+            return Ok(None);
+        }
+        let generation = {
+            let catalog_cache = self.catalog_cache.read().await;
+            if let Some(cache) = catalog_cache.1.as_ref() {
+                return Ok(Some(cache.clone()));
+            }
+            catalog_cache.0
+        };
+        let cache = Arc::new(self.read_operation(|db_ref| CatalogCache::load(db_ref)).await?);
+        let mut catalog_cache = self.catalog_cache.write().await;
+        // Catalog could be changed while loading so drop stale snapshot
+        if catalog_cache.0 == generation {
+            catalog_cache.1 = Some(cache.clone());
+        }
+        Ok(Some(cache))
+    }
+
+    fn check_not_follower(&self) -> Result<(), CubeError> {
+        if self.follower {
+            return Err(CubeError::user("Metastore follower is read-only: writes go to the primary".to_string()));
+        }
+        Ok(())
+    }
+
+    pub async fn run_upload_loop(&self) -> Result<(), CubeError> {
+        self.check_not_follower()?;
         loop {
             if !*self.upload_loop_enabled.read().await {
-                return;
+                return Ok(());
             }
             if let Err(e) = self.run_upload().await {
                 error!("Error in metastore upload loop: {}", e);
@@ -1201,12 +2257,104 @@ impl RocksMetaStore {
         }
     }
 
+    /// Keeps a read replica in sync by replaying log files uploaded by the primary. Never uploads anything.
+    pub async fn run_follower_loop(&self) {
+        loop {
+            if !*self.follower_loop_enabled.read().await {
+                return;
+            }
+            if let Err(e) = self.pull_remote_logs().await {
+                error!("Error in metastore follower loop: {}", e);
+            }
+            tokio::time::delay_for(Duration::from_secs(1)).await; // TODO config
+        }
+    }
+
+    // Sequence numbers keep growing across checkpoints and the first log of a checkpoint starts right
+    // after the last log of the previous one, so switching to a newer checkpoint only needs draining the old logs.
+    pub async fn pull_remote_logs(&self) -> Result<usize, CubeError> {
+        let current = match Self::read_remote_current(self.remote_fs.as_ref()).await? {
+            Some(current) => current,
+            None => return Ok(0)
+        };
+        let followed = self.followed_checkpoint.read().await.clone();
+        let mut applied = 0;
+        if let Some(followed) = followed.filter(|f| f != &current) {
+            let logs = self.list_logs_to_apply(&followed).await?;
+            // Retention deletes the checkpoint together with its logs
+            if self.remote_fs.list(&format!("{}/", followed)).await?.is_empty() {
+                return Err(CubeError::internal(format!(
+                    "Followed metastore checkpoint {} was deleted before its logs were applied: follower should be reloaded from {}",
+                    followed,
+                    current
+                )));
+            }
+            applied += self.apply_remote_logs(logs).await?;
+        }
+        *self.followed_checkpoint.write().await = Some(current.clone());
+        let logs = self.list_logs_to_apply(&current).await?;
+        applied += self.apply_remote_logs(logs).await?;
+        self.save_follower_state().await?;
+        if applied > 0 {
+            // Same as after the initial replay in load_from_remote_impl
+            Self::backfill_indexes(&*self.db.read().await, false)?;
+            let mut catalog_cache = self.catalog_cache.write().await;
+            *catalog_cache = (catalog_cache.0 + 1, None);
+        }
+        Ok(applied)
+    }
+
+    async fn list_logs_to_apply(&self, checkpoint: &str) -> Result<Vec<(u64, String)>, CubeError> {
+        let last_applied_log_seq = *self.last_applied_log_seq.read().await;
+        Ok(Self::list_remote_logs(self.remote_fs.as_ref(), &format!("{}-logs/", checkpoint)).await?
+            .into_iter()
+            .filter(|(seq, _)| *seq > last_applied_log_seq)
+            .collect::<Vec<_>>())
+    }
+
+    async fn apply_remote_logs(&self, logs: Vec<(u64, String)>) -> Result<usize, CubeError> {
+        for (seq, log_file) in logs.iter() {
+            let path_to_log = self.remote_fs.download_file(log_file).await?;
+            {
+                let db = self.db.write().await;
+                WriteBatchContainer::apply_from_file(&path_to_log, &db, 10000).await?; // TODO config
+            }
+            *self.last_applied_log_seq.write().await = *seq;
+            self.save_follower_state().await?;
+        }
+        Ok(logs.len())
+    }
+
+    async fn save_follower_state(&self) -> Result<(), CubeError> {
+        let followed_checkpoint = self.followed_checkpoint.read().await.clone();
+        if let Some(checkpoint) = followed_checkpoint {
+            let state_path = self.db.read().await.path().with_extension("follower");
+            let tmp_path = state_path.with_extension("follower.tmp");
+            let state = format!("{}\n{}", checkpoint, *self.last_applied_log_seq.read().await);
+            fs::write(&tmp_path, state).await?;
+            fs::rename(&tmp_path, &state_path).await?;
+        }
+        Ok(())
+    }
+
+    // Log files are named after the first sequence number they contain so they're replayed in that order
+    async fn list_remote_logs(remote_fs: &dyn RemoteFs, remote_prefix: &str) -> Result<Vec<(u64, String)>, CubeError> {
+        let re = Regex::new(r"^metastore-\d+-logs/(\d+)\.flex$").unwrap();
+        let mut logs = remote_fs.list(remote_prefix).await?.into_iter().filter_map(|f| {
+            let seq = re.captures(&f).and_then(|c| u64::from_str(c.get(1).unwrap().as_str()).ok());
+            seq.map(|seq| (seq, f))
+        }).collect::<Vec<_>>();
+        logs.sort_by_key(|(seq, _)| *seq);
+        Ok(logs)
+    }
+
     pub async fn stop_processing_loops(&self) {
-        let mut upload_loop_enabled = self.upload_loop_enabled.write().await;
-        *upload_loop_enabled = false;
+        *self.upload_loop_enabled.write().await = false;
+        *self.follower_loop_enabled.write().await = false;
     }
 
     pub async fn run_upload(&self) -> Result<(), CubeError> {
+        self.check_not_follower()?;
         let last_check_seq = self.last_check_seq().await;
         let last_db_seq = self.db.read().await.latest_sequence_number();
         if last_check_seq == last_db_seq {
@@ -1232,13 +2380,22 @@ impl RocksMetaStore {
             let file_name = self.remote_fs.local_file(&log_name).await?;
             serializer.write_to_file(&file_name).await?;
             self.remote_fs.upload_file(&log_name).await?;
-            let mut seq = self.last_upload_seq.write().await;
-            *seq = max.unwrap();
+            {
+                let mut seq = self.last_upload_seq.write().await;
+                *seq = max.unwrap();
+            }
             self.write_completed_notify.notify();
+            self.uncompacted_logs.write().await.push(log_name);
+            self.compact_remote_logs().await?;
         }
+        self.delete_expired_logs().await;
 
         let last_checkpoint_time: SystemTime = self.last_checkpoint_time.read().await.clone();
-        if last_checkpoint_time + time::Duration::from_secs(60) < SystemTime::now() {
+        let writes_since_checkpoint = *self.writes_since_checkpoint.read().await;
+        let write_limit_reached = self.config.checkpoint_write_operations
+            .map(|limit| writes_since_checkpoint >= limit)
+            .unwrap_or(false);
+        if write_limit_reached || last_checkpoint_time + self.config.checkpoint_interval < self.config.clock.now() {
             self.upload_check_point().await?;
         }
 
@@ -1248,12 +2405,64 @@ impl RocksMetaStore {
         Ok(())
     }
 
+    // Rolls the log files uploaded since the last compaction into a single one named after the last of them.
+    // Keeping the last name preserves replay order against logs uploaded later, and a reader that has
+    // applied only some of the merged logs sees a newer sequence and replays the whole rolled-up file,
+    // which leaves the same state as puts and deletes are applied in their original order.
+    // Merged files are never merged again so every log is uploaded at most twice per checkpoint.
+    pub async fn compact_remote_logs(&self) -> Result<bool, CubeError> {
+        let mut uncompacted_logs = self.uncompacted_logs.write().await;
+        if uncompacted_logs.len() <= self.config.log_compaction_threshold {
+            return Ok(false);
+        }
+
+        // Logs are written locally before upload so there's nothing to download
+        let mut merged = WriteBatchContainer::new();
+        for log_file in uncompacted_logs.iter() {
+            let path_to_log = self.remote_fs.local_file(log_file).await?;
+            merged.entries.extend(WriteBatchContainer::read_from_file(&path_to_log).await?.entries);
+        }
+        let last_log = uncompacted_logs.last().unwrap().clone();
+        let file_name = self.remote_fs.local_file(&last_log).await?;
+        merged.write_to_file(&file_name).await?;
+        self.remote_fs.upload_file(&last_log).await?;
+
+        let now = self.config.clock.now();
+        let mut pending_log_deletions = self.pending_log_deletions.write().await;
+        for log_file in uncompacted_logs[0..uncompacted_logs.len() - 1].iter() {
+            pending_log_deletions.push((now, log_file.clone()));
+        }
+        info!("Compacted {} metastore log files into {}", uncompacted_logs.len(), last_log);
+        uncompacted_logs.clear();
+        Ok(true)
+    }
+
+    // Best effort: a log left behind is replayed before the merged file covering it which is harmless
+    async fn delete_expired_logs(&self) {
+        let now = self.config.clock.now();
+        let grace_period = self.config.log_deletion_grace_period;
+        let expired = {
+            let mut pending_log_deletions = self.pending_log_deletions.write().await;
+            let (expired, pending) = pending_log_deletions.drain(..)
+                .partition::<Vec<_>, _>(|(compacted_at, _)| *compacted_at + grace_period <= now);
+            *pending_log_deletions = pending;
+            expired
+        };
+        for (_, log_file) in expired.into_iter() {
+            if let Err(e) = self.remote_fs.delete_file(&log_file).await {
+                warn!("Can't delete compacted metastore log {}: {}", log_file, e);
+            }
+        }
+    }
+
     async fn upload_check_point(&self) -> Result<(), CubeError> {
         let mut check_point_time = self.last_checkpoint_time.write().await;
         let remote_fs = self.remote_fs.clone();
         let db = self.db.write().await.clone();
-        *check_point_time = SystemTime::now();
-        RocksMetaStore::upload_checkpoint(db, remote_fs, &check_point_time).await?;
+        *check_point_time = self.config.clock.now();
+        *self.writes_since_checkpoint.write().await = 0;
+        self.uncompacted_logs.write().await.clear();
+        RocksMetaStore::upload_checkpoint(db, remote_fs, &check_point_time, self.config.snapshot_retention, self.config.clock.as_ref()).await?;
         self.write_completed_notify.notify();
         Ok(())
     }
@@ -1266,7 +2475,15 @@ impl RocksMetaStore {
         *self.last_check_seq.read().await
     }
 
-    async fn upload_checkpoint(db: Arc<DB>, remote_fs: Arc<dyn RemoteFs>, checkpoint_time: &SystemTime) -> Result<(), CubeError> {
+    fn delete_index_stats(stats_table: &IndexStatsRocksTable, index_id: u64, batch_pipe: &mut BatchPipe) -> Result<(), CubeError> {
+        let stats = stats_table.get_rows_by_index(&IndexStatsIndexKey::ByIndexId(index_id), &IndexStatsRocksIndex::IndexId)?;
+        for s in stats.into_iter() {
+            stats_table.delete(s.get_id(), batch_pipe)?;
+        }
+        Ok(())
+    }
+
+    async fn upload_checkpoint(db: Arc<DB>, remote_fs: Arc<dyn RemoteFs>, checkpoint_time: &SystemTime, snapshot_retention: Duration, clock: &dyn Clock) -> Result<(), CubeError> {
         let remote_path = RocksMetaStore::meta_store_path(checkpoint_time);
         let checkpoint_path = db.path().join("..").join(remote_path.clone());
         let path_to_move = checkpoint_path.clone();
@@ -1276,22 +2493,51 @@ impl RocksMetaStore {
             Ok(())
         }).await??;
 
-        let mut dir = fs::read_dir(checkpoint_path).await?;
+        let mut dir = fs::read_dir(checkpoint_path.clone()).await?;
 
-        let mut files_to_upload = Vec::new();
+        let mut file_names = Vec::new();
         while let Some(file) = dir.next_entry().await? {
-            let file = file.file_name();
-            files_to_upload.push(format!("{}/{}", remote_path, file.to_string_lossy()));
+            file_names.push(file.file_name().to_string_lossy().to_string());
         }
-        for v in join_all(files_to_upload.iter().map(|f| remote_fs.upload_file(&f)).collect::<Vec<_>>()).await.into_iter() {
-            v?;
+
+        // Checkpoint files are hard links to live SST files so compressed copies are written next to them
+        // and removed once uploaded to not double the local disk usage with every checkpoint
+        let to_compress = file_names.clone();
+        let compress_path = checkpoint_path.clone();
+        let compress_result = tokio::task::spawn_blocking(move || -> Result<(), CubeError> {
+            for file_name in to_compress.iter() {
+                let mut source = std::fs::File::open(compress_path.join(file_name))?;
+                let mut encoder = GzEncoder::new(std::fs::File::create(compress_path.join(format!("{}.gz", file_name)))?, Compression::default());
+                std::io::copy(&mut source, &mut encoder)?;
+                encoder.finish()?;
+            }
+            Ok(())
+        }).await?;
+
+        let upload_result = match compress_result {
+            Ok(()) => {
+                let files_to_upload = file_names.iter().map(|f| format!("{}/{}.gz", remote_path, f)).collect::<Vec<_>>();
+                join_all(files_to_upload.iter().map(|f| remote_fs.upload_file(&f)).collect::<Vec<_>>()).await
+                    .into_iter()
+                    .collect::<Result<Vec<_>, _>>()
+                    .map(|_| ())
+            }
+            Err(e) => Err(e)
+        };
+        for file_name in file_names.iter() {
+            let compressed = checkpoint_path.join(format!("{}.gz", file_name));
+            if fs::metadata(&compressed).await.is_ok() {
+                fs::remove_file(&compressed).await?;
+            }
         }
+        upload_result?;
 
         let existing_metastore_files = remote_fs.list("metastore-").await?;
+        let now_millis = clock.now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_millis();
         let to_delete = existing_metastore_files.into_iter().filter_map(|existing| {
             let path = existing.split("/").nth(0).map(|p| u128::from_str(&p.replace("metastore-", "").replace("-logs", "")));
             if let Some(Ok(millis)) = path {
-                if SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_millis() - millis > 3 * 60 * 1000 {
+                if now_millis.saturating_sub(millis) > snapshot_retention.as_millis() {
                     return Some(existing);
                 }
             }
@@ -1330,22 +2576,26 @@ impl RocksMetaStore {
 
     fn check_if_exists(name: &String, existing_keys_len: usize) -> Result<(), CubeError> {
         if existing_keys_len > 1 {
-            let e = CubeError::user(format!("Schema with name '{}' has more than one id. Something went wrong.", name));
+            let e = CubeError::corruption(format!("Schema with name '{}' has more than one id. Something went wrong.", name));
             return Err(e);
         } else if existing_keys_len == 0 {
-            let e = CubeError::user(format!("Schema with name '{}' does not exist.", name));
+            let e = CubeError::not_found(format!("Schema with name '{}' does not exist.", name));
             return Err(e);
         }
         Ok(())
     }
 
     pub fn prepare_test_metastore(test_name: &str) -> (Arc<LocalDirRemoteFs>, Arc<RocksMetaStore>) {
+        Self::prepare_test_metastore_with_config(test_name, MetaStoreConfig::default())
+    }
+
+    pub fn prepare_test_metastore_with_config(test_name: &str, config: MetaStoreConfig) -> (Arc<LocalDirRemoteFs>, Arc<RocksMetaStore>) {
         let store_path = env::current_dir().unwrap().join(format!("test-{}-local", test_name));
         let remote_store_path = env::current_dir().unwrap().join(format!("test-{}-remote", test_name));
         let _ = std::fs::remove_dir_all(store_path.clone());
         let _ = std::fs::remove_dir_all(remote_store_path.clone());
-        let remote_fs = LocalDirRemoteFs::new(store_path.clone(), remote_store_path.clone());
-        let meta_store = RocksMetaStore::new(store_path.clone().join("metastore").as_path(), remote_fs.clone());
+        let remote_fs = LocalDirRemoteFs::new(remote_store_path.clone(), store_path.clone());
+        let meta_store = RocksMetaStore::with_listener(store_path.clone().join("metastore").as_path(), vec![], remote_fs.clone(), config).unwrap();
         (remote_fs, meta_store)
     }
 
@@ -1356,6 +2606,47 @@ impl RocksMetaStore {
         let _ = std::fs::remove_dir_all(remote_store_path.clone());
     }
 
+    pub async fn export_catalog_parquet(&self, dir: &str) -> Result<(), CubeError> {
+        let dir = dir.to_string();
+        fs::create_dir_all(&dir).await?;
+        let data_frames = self.read_operation(|db_ref| -> Result<Vec<(&'static str, DataFrame)>, CubeError> {
+            let snapshot = db_ref.snapshot();
+            Ok(vec![
+                ("schemas", DataFrame::from(SchemaRocksTable::new(db_ref.clone()).snapshot_rows(&snapshot)?)),
+                ("tables", DataFrame::from(TableRocksTable::new(db_ref.clone()).snapshot_rows(&snapshot)?)),
+                ("indexes", DataFrame::from(IndexRocksTable::new(db_ref.clone()).snapshot_rows(&snapshot)?)),
+                ("partitions", DataFrame::from(PartitionRocksTable::new(db_ref.clone()).snapshot_rows(&snapshot)?)),
+                ("chunks", DataFrame::from(ChunkRocksTable::new(db_ref.clone()).snapshot_rows(&snapshot)?)),
+            ])
+        }).await?;
+
+        tokio::task::spawn_blocking(move || -> Result<(), CubeError> {
+            for (name, data_frame) in data_frames.into_iter() {
+                let file_name = PathBuf::from(&dir).join(format!("{}.parquet", name));
+                let index = Index::new(name.to_string(), 0, data_frame.get_columns().clone(), 0);
+                let store = ParquetTableStore::new(index, 16384);
+                store.merge_rows(None, vec![file_name.to_string_lossy().to_string()], data_frame.into_rows(), 0)?;
+            }
+            Ok(())
+        }).await??;
+
+        Ok(())
+    }
+
+    // Counts keys under the table prefix without deserializing rows
+    fn count_table_rows(db: &DB, table_id: TableId) -> u64 {
+        let mut opts = ReadOptions::default();
+        opts.set_prefix_same_as_start(true);
+        let mut iterator = db.raw_iterator_opt(opts);
+        iterator.seek(&RowKey::Table(table_id, 0).to_bytes()[0..get_fixed_prefix()]);
+        let mut count = 0;
+        while iterator.valid() {
+            count += 1;
+            iterator.next();
+        }
+        count
+    }
+
     async fn has_pending_changes(&self) -> Result<bool, CubeError> {
         let db = self.db.read().await;
         Ok(db.get_updates_since(self.last_upload_seq().await)?.next().is_some())
@@ -1371,6 +2662,24 @@ impl MetaStore for RocksMetaStore {
         Ok(())
     }
 
+    async fn get_meta_store_stats(&self) -> Result<MetaStoreStats, CubeError> {
+        let last_upload_seq = self.last_upload_seq().await;
+        let last_check_seq = self.last_check_seq().await;
+        self.read_operation(move |db_ref| {
+            let row_counts = ALL_TABLE_IDS.iter().cloned()
+                .map(|table_id| (table_id, RocksMetaStore::count_table_rows(&db_ref, table_id)))
+                .collect::<HashMap<_, _>>();
+            Ok(MetaStoreStats {
+                row_counts,
+                latest_sequence_number: db_ref.latest_sequence_number(),
+                last_upload_seq,
+                last_check_seq,
+                // The iterator starts at the batch covering last_upload_seq which is already uploaded
+                pending_updates: db_ref.get_updates_since(last_upload_seq)?.filter(|(seq, _)| *seq > last_upload_seq).count() as u64
+            })
+        }).await
+    }
+
     fn schemas_table(&self) -> Box<dyn MetaStoreTable<T=Schema>> {
         Box::new(MetaStoreTableImpl {
             rocks_meta_store: self.clone(),
@@ -1398,6 +2707,56 @@ impl MetaStore for RocksMetaStore {
         }).await
     }
 
+    async fn get_schemas_with_counts(&self) -> Result<Vec<(IdRow<Schema>, u64, u64)>, CubeError> {
+        self.read_operation(move |db_ref| {
+            let schemas = SchemaRocksTable::new(db_ref.clone()).all_rows()?;
+            let tables = TableRocksTable::new(db_ref.clone()).all_rows()?;
+            let indexes = IndexRocksTable::new(db_ref).all_rows()?;
+
+            let mut index_counts: HashMap<u64, u64> = HashMap::new();
+            for index in indexes.iter() {
+                *index_counts.entry(index.get_row().table_id()).or_insert(0) += 1;
+            }
+            let mut counts: HashMap<u64, (u64, u64)> = HashMap::new();
+            for table in tables.iter() {
+                let entry = counts.entry(table.get_row().get_schema_id()).or_insert((0, 0));
+                entry.0 += 1;
+                entry.1 += index_counts.get(&table.get_id()).cloned().unwrap_or(0);
+            }
+            Ok(schemas.into_iter().map(|s| {
+                let (table_count, index_count) = counts.get(&s.get_id()).cloned().unwrap_or((0, 0));
+                (s, table_count, index_count)
+            }).collect::<Vec<_>>())
+        }).await
+    }
+
+    async fn get_schema_usage(&self, schema_id: u64) -> Result<SchemaUsage, CubeError> {
+        self.read_operation(move |db_ref| {
+            SchemaRocksTable::new(db_ref.clone()).get_row_or_not_found(schema_id)?;
+            let table_ids = TableRocksTable::new(db_ref.clone()).all_rows()?.into_iter()
+                .filter(|t| t.get_row().get_schema_id() == schema_id)
+                .map(|t| t.get_id())
+                .collect::<HashSet<_>>();
+            let index_ids = IndexRocksTable::new(db_ref.clone()).all_rows()?.into_iter()
+                .filter(|i| table_ids.contains(&i.get_row().table_id()) && i.get_row().get_name() == "default")
+                .map(|i| i.get_id())
+                .collect::<HashSet<_>>();
+            let partitions = PartitionRocksTable::new(db_ref.clone()).all_rows()?.into_iter()
+                .filter(|p| p.get_row().is_active() && index_ids.contains(&p.get_row().get_index_id()))
+                .collect::<Vec<_>>();
+            let partition_ids = partitions.iter().map(|p| p.get_id()).collect::<HashSet<_>>();
+            let chunk_rows: u64 = ChunkRocksTable::new(db_ref).all_rows()?.into_iter()
+                .filter(|c| c.get_row().active() && partition_ids.contains(&c.get_row().get_partition_id()))
+                .map(|c| c.get_row().get_row_count())
+                .sum();
+            let partition_rows: u64 = partitions.iter().map(|p| p.get_row().main_table_row_count()).sum();
+            Ok(SchemaUsage {
+                table_count: table_ids.len() as u64,
+                total_rows: partition_rows + chunk_rows
+            })
+        }).await
+    }
+
     async fn get_schema_by_id(&self, schema_id: u64) -> Result<IdRow<Schema>, CubeError> {
         self.read_operation(move |db_ref| {
             let table = SchemaRocksTable::new(db_ref);
@@ -1415,6 +2774,11 @@ impl MetaStore for RocksMetaStore {
     }
 
     async fn get_schema(&self, schema_name: String) -> Result<IdRow<Schema>, CubeError> {
+        if let Some(cache) = self.cached_catalog().await? {
+            if let Some(schema) = cache.schemas.get(&schema_name) {
+                return Ok(schema.clone());
+            }
+        }
         self.read_operation(move |db_ref| {
             let table = SchemaRocksTable::new(db_ref);
             let existing_keys = table.get_row_ids_by_index(&schema_name, &SchemaRocksIndex::Name)?;
@@ -1425,7 +2789,7 @@ impl MetaStore for RocksMetaStore {
                 return Ok(schema);
             }
 
-            let e = CubeError::user(format!("Schema with name '{}' does not exist.", schema_name));
+            let e = CubeError::not_found(format!("Schema with name '{}' does not exist.", schema_name));
             Err(e)
         }).await
     }
@@ -1441,7 +2805,12 @@ impl MetaStore for RocksMetaStore {
             let old_schema = table.get_row(schema_id)?.unwrap();
             let mut new_schema = old_schema.clone();
             new_schema.row.set_name(&new_schema_name);
-            let id_row = table.update(schema_id, new_schema.row, &old_schema.row, batch_pipe)?;
+            let event = MetaStoreEvent::SchemaRenamed {
+                id: schema_id,
+                old_name: old_schema.get_row().get_name().clone(),
+                new_name: new_schema_name
+            };
+            let id_row = table.update_with_event(schema_id, new_schema.row, &old_schema.row, event, batch_pipe)?;
             Ok(id_row)
         }).await
     }
@@ -1453,7 +2822,12 @@ impl MetaStore for RocksMetaStore {
             let old_schema = table.get_row(schema_id)?.unwrap();
             let mut new_schema = old_schema.clone();
             new_schema.row.set_name(&new_schema_name);
-            let id_row = table.update(schema_id, new_schema.row, &old_schema.row, batch_pipe)?;
+            let event = MetaStoreEvent::SchemaRenamed {
+                id: schema_id,
+                old_name: old_schema.get_row().get_name().clone(),
+                new_name: new_schema_name
+            };
+            let id_row = table.update_with_event(schema_id, new_schema.row, &old_schema.row, event, batch_pipe)?;
 
             Ok(id_row)
         }).await
@@ -1488,7 +2862,7 @@ impl MetaStore for RocksMetaStore {
         })
     }
 
-    async fn create_table(&self, schema_name: String, table_name: String, columns: Vec<Column>, location: Option<String>, import_format: Option<ImportFormat>, indexes: Vec<IndexDef>) -> Result<IdRow<Table>, CubeError> {
+    async fn create_table(&self, schema_name: String, table_name: String, columns: Vec<Column>, location: Option<String>, import_format: Option<ImportFormat>, indexes: Vec<IndexDef>, initial_partitions: Vec<(Option<Row>, Option<Row>)>) -> Result<IdRow<Table>, CubeError> {
         self.write_operation(move |db_ref, batch_pipe| {
             let rocks_table = TableRocksTable::new(db_ref.clone());
             let rocks_index = IndexRocksTable::new(db_ref.clone());
@@ -1511,14 +2885,28 @@ impl MetaStore for RocksMetaStore {
             }
             let index = Index::new("default".to_string(), table_id.get_id(), index_cols, sort_key_size);
             let index_id = rocks_index.insert(index, batch_pipe)?;
-            let partition = Partition::new(index_id.id, None, None);
-            let _ = rocks_partition.insert(partition, batch_pipe)?;
+            if initial_partitions.is_empty() {
+                let partition = Partition::new(index_id.id, None, None);
+                let _ = rocks_partition.insert(partition, batch_pipe)?;
+            } else {
+                for (min_value, max_value) in initial_partitions.into_iter() {
+                    let partition = Partition::new(index_id.id, min_value, max_value);
+                    let _ = rocks_partition.insert(partition, batch_pipe)?;
+                }
+            }
 
             Ok(table_id)
         }).await
     }
 
     async fn get_table(&self, schema_name: String, table_name: String) -> Result<IdRow<Table>, CubeError> {
+        if let Some(cache) = self.cached_catalog().await? {
+            let table = cache.schemas.get(&schema_name)
+                .and_then(|schema| cache.tables_by_name.get(&(schema.get_id(), table_name.to_string())));
+            if let Some(table) = table {
+                return Ok(table.clone());
+            }
+        }
         self.read_operation(move |db_ref| {
             let rocks_table = TableRocksTable::new(db_ref.clone());
             let rocks_schema = SchemaRocksTable::new(db_ref);
@@ -1529,6 +2917,33 @@ impl MetaStore for RocksMetaStore {
         }).await
     }
 
+    async fn resolve_tables(&self, refs: Vec<(String, String)>) -> Result<HashMap<(String, String), IdRow<Table>>, CubeError> {
+        self.read_operation(move |db_ref| {
+            let rocks_table = TableRocksTable::new(db_ref.clone());
+            let rocks_schema = SchemaRocksTable::new(db_ref);
+            let mut schema_ids: HashMap<String, Option<u64>> = HashMap::new();
+            let mut res = HashMap::new();
+            for (schema_name, table_name) in refs.into_iter() {
+                let schema_id = match schema_ids.get(&schema_name) {
+                    Some(id) => *id,
+                    None => {
+                        let id = rocks_schema.get_rows_by_index(&schema_name, &SchemaRocksIndex::Name)?
+                            .into_iter().next().map(|s| s.get_id());
+                        schema_ids.insert(schema_name.to_string(), id);
+                        id
+                    }
+                };
+                if let Some(schema_id) = schema_id {
+                    let index_key = TableIndexKey::ByName(schema_id, table_name.to_string());
+                    if let Some(table) = rocks_table.get_rows_by_index(&index_key, &TableRocksIndex::Name)?.into_iter().next() {
+                        res.insert((schema_name, table_name), table);
+                    }
+                }
+            }
+            Ok(res)
+        }).await
+    }
+
     async fn get_table_by_id(&self, table_id: u64) -> Result<IdRow<Table>, CubeError> {
         self.read_operation(move |db_ref| {
             TableRocksTable::new(db_ref.clone()).get_row_or_not_found(table_id)
@@ -1536,6 +2951,9 @@ impl MetaStore for RocksMetaStore {
     }
 
     async fn get_tables(&self) -> Result<Vec<IdRow<Table>>, CubeError> {
+        if let Some(cache) = self.cached_catalog().await? {
+            return Ok(cache.tables.clone());
+        }
         self.read_operation(|db_ref| {
             TableRocksTable::new(db_ref).all_rows()
         }).await
@@ -1553,15 +2971,54 @@ impl MetaStore for RocksMetaStore {
         }).await
     }
 
-    async fn drop_table(&self, table_id: u64) -> Result<IdRow<Table>, CubeError> {
+    async fn get_tables_with_path_for_schema(&self, schema_id: u64) -> Result<Vec<TablePath>, CubeError> {
+        self.read_operation(move |db_ref| {
+            let tables = TableRocksTable::new(db_ref.clone()).get_rows_by_index(
+                &TableIndexKey::BySchemaId(schema_id),
+                &TableRocksIndex::SchemaId
+            )?;
+            let schemas = SchemaRocksTable::new(db_ref);
+            Ok(schemas.build_path_rows(
+                tables,
+                |t| t.get_row().get_schema_id(),
+                |table, schema| TablePath { table, schema }
+            )?)
+        }).await
+    }
+
+    async fn get_tables_using_column_type(&self, col_type: ColumnType) -> Result<Vec<IdRow<Table>>, CubeError> {
+        self.read_operation(move |db_ref| {
+            Ok(TableRocksTable::new(db_ref).all_rows()?.into_iter()
+                .filter(|t| t.get_row().get_columns().iter().any(|c| c.get_column_type() == &col_type))
+                .collect::<Vec<_>>())
+        }).await
+    }
+
+    async fn get_table_column_types(&self, table_id: u64) -> Result<Vec<(String, ColumnType)>, CubeError> {
+        self.read_operation(move |db_ref| {
+            let table = TableRocksTable::new(db_ref).get_row_or_not_found(table_id)?;
+            let mut columns = table.get_row().get_columns().clone();
+            columns.sort_by_key(|c| c.get_index());
+            Ok(columns.into_iter().map(|c| (c.get_name().clone(), c.get_column_type().clone())).collect::<Vec<_>>())
+        }).await
+    }
+
+    async fn drop_table(&self, table_id: u64, coalesce_events: bool) -> Result<IdRow<Table>, CubeError> {
         self.write_operation(move |db_ref, batch_pipe| {
+            if coalesce_events {
+                batch_pipe.coalesce_deletes(TableId::Chunks);
+                batch_pipe.coalesce_deletes(TableId::Partitions);
+                batch_pipe.coalesce_deletes(TableId::Indexes);
+            }
             let tables_table = TableRocksTable::new(db_ref.clone());
             let indexes_table = IndexRocksTable::new(db_ref.clone());
             let partitions_table = PartitionRocksTable::new(db_ref.clone());
-            let chunks_table = ChunkRocksTable::new(db_ref);
+            let chunks_table = ChunkRocksTable::new(db_ref.clone());
+            let stats_table = IndexStatsRocksTable::new(db_ref);
 
             let indexes = indexes_table.get_rows_by_index(&IndexIndexKey::TableId(table_id), &IndexRocksIndex::TableID)?;
             for index in indexes.into_iter() {
+                RocksMetaStore::delete_index_stats(&stats_table, index.get_id(), batch_pipe)?;
                 let partitions = partitions_table.get_rows_by_index(&PartitionIndexKey::ByIndexId(index.get_id()), &PartitionRocksIndex::IndexId)?;
                 for partition in partitions.into_iter() {
                     let chunks = chunks_table.get_rows_by_index(&ChunkIndexKey::ByPartitionId(partition.get_id()), &ChunkRocksIndex::PartitionId)?;
@@ -1576,42 +3033,143 @@ impl MetaStore for RocksMetaStore {
         }).await
     }
 
-    fn partition_table(&self) -> Box<dyn MetaStoreTable<T=Partition>> {
-        Box::new(MetaStoreTableImpl {
-            rocks_meta_store: self.clone(),
-            rocks_table_fn: |db| PartitionRocksTable::new(db)
-        })
-    }
-
-    async fn create_partition(&self, partition: Partition) -> Result<IdRow<Partition>, CubeError> {
+    async fn add_column(&self, table_id: u64, column: Column) -> Result<IdRow<Table>, CubeError> {
         self.write_operation(move |db_ref, batch_pipe| {
-            let table = PartitionRocksTable::new(db_ref.clone());
-            let row_id = table.insert(partition, batch_pipe)?;
-            Ok(row_id)
-        }).await
-    }
-
-    async fn get_partition(&self, partition_id: u64) -> Result<IdRow<Partition>, CubeError> {
-        self.read_operation(move |db_ref| {
-            PartitionRocksTable::new(db_ref).get_row_or_not_found(partition_id)
-        }).await
-    }
+            let tables_table = TableRocksTable::new(db_ref.clone());
+            let indexes_table = IndexRocksTable::new(db_ref);
 
-    async fn get_partition_for_compaction(&self, partition_id: u64) -> Result<(IdRow<Partition>, IdRow<Index>), CubeError> {
-        self.read_operation(move |db_ref| {
-            let partition = PartitionRocksTable::new(db_ref.clone()).get_row(partition_id)?
-                .ok_or(CubeError::internal(format!("Partition is not found: {}", partition_id)))?;
-            let index = IndexRocksTable::new(db_ref.clone()).get_row(partition.get_row().get_index_id())?
-                .ok_or(CubeError::internal(format!("Index {} is not found for partition: {}", partition.get_row().get_index_id(), partition_id)))?;
-            if !partition.get_row().is_active() {
-                return Err(CubeError::internal(format!("Cannot compact inactive partition: {:?}", partition.get_row())))
+            let table = tables_table.get_row_or_not_found(table_id)?;
+            if table.get_row().get_columns().iter().any(|c| c.get_name() == column.get_name()) {
+                return Err(CubeError::user(format!(
+                    "Column {} already exists in table {}", column.get_name(), table.get_row().get_table_name()
+                )));
             }
-            Ok((partition, index))
+            let indexes = indexes_table.get_rows_by_index(&IndexIndexKey::TableId(table_id), &IndexRocksIndex::TableID)?;
+            for index in indexes.into_iter() {
+                indexes_table.update(index.get_id(), index.get_row().add_column(column.clone()), index.get_row(), batch_pipe)?;
+            }
+            Ok(tables_table.update(table_id, table.get_row().add_column(column), table.get_row(), batch_pipe)?)
         }).await
     }
 
-    async fn get_partition_chunk_sizes(&self, partition_id: u64) -> Result<u64, CubeError> {
-        let chunks = self.get_chunks_by_partition(partition_id).await?;
+    async fn truncate_table(&self, table_id: u64) -> Result<(), CubeError> {
+        self.write_operation(move |db_ref, batch_pipe| {
+            let tables_table = TableRocksTable::new(db_ref.clone());
+            let indexes_table = IndexRocksTable::new(db_ref.clone());
+            let partitions_table = PartitionRocksTable::new(db_ref.clone());
+            let chunks_table = ChunkRocksTable::new(db_ref.clone());
+            let wals_table = WALRocksTable::new(db_ref.clone());
+            let stats_table = IndexStatsRocksTable::new(db_ref);
+
+            tables_table.get_row_or_not_found(table_id)?;
+            let indexes = indexes_table.get_rows_by_index(&IndexIndexKey::TableId(table_id), &IndexRocksIndex::TableID)?;
+            for index in indexes.into_iter() {
+                RocksMetaStore::delete_index_stats(&stats_table, index.get_id(), batch_pipe)?;
+                let partitions = partitions_table.get_rows_by_index(&PartitionIndexKey::ByIndexId(index.get_id()), &PartitionRocksIndex::IndexId)?;
+                for partition in partitions.into_iter() {
+                    let chunks = chunks_table.get_rows_by_index(&ChunkIndexKey::ByPartitionId(partition.get_id()), &ChunkRocksIndex::PartitionId)?;
+                    for chunk in chunks.into_iter() {
+                        chunks_table.delete(chunk.get_id(), batch_pipe)?;
+                    }
+                    partitions_table.delete(partition.get_id(), batch_pipe)?;
+                }
+                partitions_table.insert(Partition::new(index.get_id(), None, None), batch_pipe)?;
+            }
+            let wals = wals_table.get_rows_by_index(&WALIndexKey::ByTable(table_id), &WALRocksIndex::TableID)?;
+            for wal in wals.into_iter() {
+                wals_table.delete(wal.get_id(), batch_pipe)?;
+            }
+            Ok(())
+        }).await
+    }
+
+    async fn delete_partition(&self, partition_id: u64) -> Result<IdRow<Partition>, CubeError> {
+        self.write_operation(move |db_ref, batch_pipe| {
+            let partitions_table = PartitionRocksTable::new(db_ref.clone());
+            let chunks_table = ChunkRocksTable::new(db_ref);
+
+            let partition = partitions_table.get_row_or_not_found(partition_id)?;
+            let chunks = chunks_table.get_rows_by_index(&ChunkIndexKey::ByPartitionId(partition_id), &ChunkRocksIndex::PartitionId)?;
+            if let Some(chunk) = chunks.iter().find(|c| c.get_row().active()) {
+                return Err(CubeError::user(format!(
+                    "Can't delete partition {} as it has active chunk {}", partition_id, chunk.get_id()
+                )));
+            }
+            for chunk in chunks.into_iter() {
+                chunks_table.delete(chunk.get_id(), batch_pipe)?;
+            }
+            partitions_table.delete(partition.get_id(), batch_pipe)?;
+            Ok(partition)
+        }).await
+    }
+
+    fn partition_table(&self) -> Box<dyn MetaStoreTable<T=Partition>> {
+        Box::new(MetaStoreTableImpl {
+            rocks_meta_store: self.clone(),
+            rocks_table_fn: |db| PartitionRocksTable::new(db)
+        })
+    }
+
+    async fn create_partition(&self, partition: Partition) -> Result<IdRow<Partition>, CubeError> {
+        self.write_operation(move |db_ref, batch_pipe| {
+            let table = PartitionRocksTable::new(db_ref.clone());
+            let row_id = table.insert(partition, batch_pipe)?;
+            Ok(row_id)
+        }).await
+    }
+
+    async fn get_partition(&self, partition_id: u64) -> Result<IdRow<Partition>, CubeError> {
+        self.read_operation(move |db_ref| {
+            PartitionRocksTable::new(db_ref).get_row_or_not_found(partition_id)
+        }).await
+    }
+
+    async fn get_all_partitions(&self, active_only: bool) -> Result<Vec<IdRow<Partition>>, CubeError> {
+        self.read_operation(move |db_ref| {
+            Ok(PartitionRocksTable::new(db_ref).all_rows()?.into_iter()
+                .filter(|p| !active_only || p.get_row().is_active())
+                .collect::<Vec<_>>())
+        }).await
+    }
+
+    async fn get_partition_for_compaction(&self, partition_id: u64) -> Result<(IdRow<Partition>, IdRow<Index>), CubeError> {
+        self.read_operation(move |db_ref| {
+            let partition = PartitionRocksTable::new(db_ref.clone()).get_row(partition_id)?
+                .ok_or(CubeError::internal(format!("Partition is not found: {}", partition_id)))?;
+            let index = IndexRocksTable::new(db_ref.clone()).get_row(partition.get_row().get_index_id())?
+                .ok_or(CubeError::internal(format!("Index {} is not found for partition: {}", partition.get_row().get_index_id(), partition_id)))?;
+            if !partition.get_row().is_active() {
+                return Err(CubeError::internal(format!("Cannot compact inactive partition: {:?}", partition.get_row())))
+            }
+            Ok((partition, index))
+        }).await
+    }
+
+    async fn get_compaction_input(&self, partition_id: u64, min_rows: Option<u64>) -> Result<CompactionInput, CubeError> {
+        self.read_operation(move |db_ref| {
+            let partition = PartitionRocksTable::new(db_ref.clone()).get_row(partition_id)?
+                .ok_or(CubeError::internal(format!("Partition is not found: {}", partition_id)))?;
+            let index = IndexRocksTable::new(db_ref.clone()).get_row(partition.get_row().get_index_id())?
+                .ok_or(CubeError::internal(format!("Index {} is not found for partition: {}", partition.get_row().get_index_id(), partition_id)))?;
+            if !partition.get_row().is_active() {
+                return Err(CubeError::internal(format!("Cannot compact inactive partition: {:?}", partition.get_row())))
+            }
+            let chunks = ChunkRocksTable::new(db_ref).get_rows_by_index(
+                &ChunkIndexKey::ByPartitionId(partition_id),
+                &ChunkRocksIndex::PartitionId
+            )?.into_iter().filter(|c| c.get_row().uploaded() && c.get_row().active()).collect::<Vec<_>>();
+            if let Some(min_rows) = min_rows {
+                let chunks_row_count = chunks.iter().map(|c| c.get_row().get_row_count()).sum::<u64>();
+                if chunks_row_count < min_rows {
+                    return Ok(CompactionInput::NotWorthCompacting { chunks_row_count });
+                }
+            }
+            Ok(CompactionInput::Compact(partition, index, chunks))
+        }).await
+    }
+
+    async fn get_partition_chunk_sizes(&self, partition_id: u64) -> Result<u64, CubeError> {
+        let chunks = self.get_chunks_by_partition(partition_id).await?;
         Ok(chunks.iter().map(|r| r.get_row().row_count).sum())
     }
 
@@ -1620,32 +3178,56 @@ impl MetaStore for RocksMetaStore {
         current_active: Vec<u64>,
         new_active: Vec<u64>,
         compacted_chunk_ids: Vec<u64>,
-        new_active_min_max: Vec<(u64, (Option<Row>, Option<Row>))>
+        new_active_min_max: Vec<(u64, (Option<Row>, Option<Row>))>,
+        new_active_stats: Option<IndexStats>,
+        job_id: Option<u64>
     ) -> Result<(), CubeError> {
         self.write_operation(move |db_ref, batch_pipe| {
             let table = PartitionRocksTable::new(db_ref.clone());
             let chunk_table = ChunkRocksTable::new(db_ref.clone());
+            let stats_table = IndexStatsRocksTable::new(db_ref.clone());
 
             for current in current_active.iter() {
                 let current_partition = table.get_row(*current)?
                     .ok_or(CubeError::internal(format!("Current partition is not found during swap active: {}", current)))?;
                 if !current_partition.get_row().is_active() {
-                    return Err(CubeError::internal(format!("Current partition is not active: {:?}", current_partition.get_row())));
+                    return Err(CubeError::conflict(format!("Current partition is not active: {:?}", current_partition.get_row())));
                 }
-                table.update(current_partition.get_id(), current_partition.get_row().to_active(false), current_partition.get_row(), batch_pipe)?;
+                table.update(current_partition.get_id(), current_partition.get_row().to_active_by_job(false, job_id), current_partition.get_row(), batch_pipe)?;
             }
 
             for (new, (count, (min_value, max_value))) in new_active.iter().zip(new_active_min_max.into_iter()) {
                 let new_partition = table.get_row(*new)?
                     .ok_or(CubeError::internal(format!("New partition is not found during swap active: {}", new)))?;
                 if new_partition.get_row().is_active() {
-                    return Err(CubeError::internal(format!("New partition is already active: {:?}", new_partition.get_row())));
+                    return Err(CubeError::conflict(format!("New partition is already active: {:?}", new_partition.get_row())));
                 }
-                table.update(new_partition.get_id(), new_partition.get_row().to_active(true).update_min_max_and_row_count(min_value, max_value, count), new_partition.get_row(), batch_pipe)?;
+                table.update(new_partition.get_id(), new_partition.get_row().to_active_by_job(true, job_id).update_min_max_and_row_count(min_value, max_value, count), new_partition.get_row(), batch_pipe)?;
             }
 
             for chunk_id in compacted_chunk_ids.iter() {
-                chunk_table.update_with_fn(*chunk_id, |row| row.deactivate(), batch_pipe)?;
+                let chunk = chunk_table.get_row_or_not_found(*chunk_id)?;
+                if !current_active.contains(&chunk.get_row().get_partition_id()) {
+                    return Err(CubeError::internal(format!(
+                        "Compacted chunk {} belongs to partition {} which is not swapped out: {:?}",
+                        chunk_id,
+                        chunk.get_row().get_partition_id(),
+                        current_active
+                    )));
+                }
+                chunk_table.update(*chunk_id, chunk.get_row().deactivate(), chunk.get_row(), batch_pipe)?;
+            }
+
+            if let Some(stats) = new_active_stats {
+                let existing = stats_table.get_rows_by_index(&IndexStatsIndexKey::ByIndexId(stats.get_index_id()), &IndexStatsRocksIndex::IndexId)?;
+                match existing.into_iter().next() {
+                    Some(existing) => {
+                        stats_table.update(existing.get_id(), existing.get_row().merge(&stats), existing.get_row(), batch_pipe)?;
+                    }
+                    None => {
+                        stats_table.insert(stats, batch_pipe)?;
+                    }
+                }
             }
 
             Ok(())
@@ -1659,6 +3241,40 @@ impl MetaStore for RocksMetaStore {
         })
     }
 
+    async fn delete_index(&self, index_id: u64, force: bool) -> Result<IdRow<Index>, CubeError> {
+        self.write_operation(move |db_ref, batch_pipe| {
+            let indexes_table = IndexRocksTable::new(db_ref.clone());
+            let partitions_table = PartitionRocksTable::new(db_ref.clone());
+            let chunks_table = ChunkRocksTable::new(db_ref.clone());
+            let stats_table = IndexStatsRocksTable::new(db_ref);
+
+            let index = indexes_table.get_row_or_not_found(index_id)?;
+            if index.get_row().get_name() == "default" && !force {
+                return Err(CubeError::user(format!(
+                    "Can't delete default index {} of table {}", index_id, index.get_row().table_id()
+                )));
+            }
+            let partitions = partitions_table.get_rows_by_index(&PartitionIndexKey::ByIndexId(index_id), &PartitionRocksIndex::IndexId)?;
+            for partition in partitions.into_iter() {
+                let chunks = chunks_table.get_rows_by_index(&ChunkIndexKey::ByPartitionId(partition.get_id()), &ChunkRocksIndex::PartitionId)?;
+                for chunk in chunks.into_iter() {
+                    chunks_table.delete(chunk.get_id(), batch_pipe)?;
+                }
+                partitions_table.delete(partition.get_id(), batch_pipe)?;
+            }
+            RocksMetaStore::delete_index_stats(&stats_table, index_id, batch_pipe)?;
+            indexes_table.delete(index_id, batch_pipe)?;
+            Ok(index)
+        }).await
+    }
+
+    async fn get_index_stats(&self, index_id: u64) -> Result<Option<IdRow<IndexStats>>, CubeError> {
+        self.read_operation(move |db_ref| {
+            let stats_table = IndexStatsRocksTable::new(db_ref);
+            Ok(stats_table.get_rows_by_index(&IndexStatsIndexKey::ByIndexId(index_id), &IndexStatsRocksIndex::IndexId)?.into_iter().next())
+        }).await
+    }
+
     async fn get_default_index(&self, table_id: u64) -> Result<IdRow<Index>, CubeError> {
         self.read_operation(move |db_ref| {
             let index = IndexRocksTable::new(db_ref);
@@ -1667,13 +3283,47 @@ impl MetaStore for RocksMetaStore {
         }).await
     }
 
+    async fn get_default_index_record_batch_schema(&self, table_id: u64) -> Result<arrow::datatypes::SchemaRef, CubeError> {
+        let index = self.get_default_index(table_id).await?;
+        let fields = index.get_row().columns().iter().map(|c| c.arrow_field()).collect::<Result<Vec<_>, _>>()?;
+        Ok(Arc::new(arrow::datatypes::Schema::new(fields)))
+    }
+
     async fn get_table_indexes(&self, table_id: u64) -> Result<Vec<IdRow<Index>>, CubeError> {
+        if let Some(cache) = self.cached_catalog().await? {
+            if let Some(indexes) = cache.indexes_by_table.get(&table_id) {
+                return Ok(indexes.clone());
+            }
+        }
         self.read_operation(move |db_ref| {
             let index_table = IndexRocksTable::new(db_ref);
             Ok(index_table.get_rows_by_index(&IndexIndexKey::TableId(table_id), &IndexRocksIndex::TableID)?)
         }).await
     }
 
+    async fn get_table_index_defs(&self, table_id: u64) -> Result<Vec<IndexDef>, CubeError> {
+        Ok(self.get_table_indexes(table_id).await?.into_iter()
+            .filter(|i| i.get_row().get_name() != "default")
+            .map(|i| i.get_row().to_index_def())
+            .collect::<Vec<_>>())
+    }
+
+    async fn get_create_table_spec(&self, table_id: u64) -> Result<CreateTableSpec, CubeError> {
+        let table = self.get_table_by_id(table_id).await?;
+        let schema = self.get_schema_by_id(table.get_row().get_schema_id()).await?;
+        let mut indexes = self.get_table_index_defs(table_id).await?;
+        indexes.sort_by(|a, b| a.name.cmp(&b.name));
+        let table = table.get_row();
+        Ok(CreateTableSpec {
+            schema_name: schema.get_row().get_name().clone(),
+            table_name: table.get_table_name().clone(),
+            columns: table.get_columns().clone(),
+            location: table.location().clone(),
+            import_format: table.import_format().clone(),
+            indexes
+        })
+    }
+
     async fn get_active_partitions_by_index_id(&self, index_id: u64) -> Result<Vec<IdRow<Partition>>, CubeError> {
         self.read_operation(move |db_ref| {
             let rocks_partition = PartitionRocksTable::new(db_ref);
@@ -1685,6 +3335,194 @@ impl MetaStore for RocksMetaStore {
         }).await
     }
 
+    async fn stream_active_partitions_by_index_id(&self, index_id: u64) -> Result<mpsc::Receiver<Result<IdRow<Partition>, CubeError>>, CubeError> {
+        let db = self.db.read().await.clone();
+        let (mut sender, receiver) = mpsc::channel(1000); // TODO config
+        // Not awaited: the scan is paced by the receiver and stops as soon as it's dropped
+        tokio::task::spawn_blocking(move || {
+            let rocks_partition = PartitionRocksTable::new(db);
+            let res = rocks_partition.scan_rows_by_index(
+                &PartitionIndexKey::ByIndexId(index_id),
+                &PartitionRocksIndex::IndexId,
+                |partition| {
+                    if !partition.get_row().is_active() {
+                        return Ok(true);
+                    }
+                    Ok(futures::executor::block_on(sender.send(Ok(partition))).is_ok())
+                }
+            );
+            if let Err(e) = res {
+                let _ = futures::executor::block_on(sender.send(Err(e)));
+            }
+        });
+        Ok(receiver)
+    }
+
+    async fn get_unbounded_partitions(&self, index_id: u64) -> Result<Vec<IdRow<Partition>>, CubeError> {
+        self.read_operation(move |db_ref| {
+            Ok(PartitionRocksTable::new(db_ref).get_rows_by_index(
+                &PartitionIndexKey::ByMinValue(index_id, None),
+                &PartitionRocksIndex::MinValue
+            )?.into_iter().filter(|r| {
+                let partition = r.get_row();
+                partition.is_active() && partition.get_max_val().is_none() && partition.main_table_row_count() > 0
+            }).collect::<Vec<_>>())
+        }).await
+    }
+
+    async fn get_active_partitions_for_range(&self, index_id: u64, from: Option<Row>, to: Option<Row>) -> Result<Vec<IdRow<Partition>>, CubeError> {
+        self.read_operation(move |db_ref| {
+            let index = IndexRocksTable::new(db_ref.clone()).get_row_or_not_found(index_id)?;
+            let sort_key_size = index.get_row().sort_key_size();
+            let partitions = PartitionRocksTable::new(db_ref).get_rows_by_index(
+                &PartitionIndexKey::ByIndexId(index_id),
+                &PartitionRocksIndex::IndexId
+            )?;
+            // Missing partition bounds stand for infinity so they always overlap an open range side
+            Ok(partitions.into_iter().filter(|r| {
+                let partition = r.get_row();
+                let ends_after_from = match (&from, partition.get_max_val()) {
+                    (Some(from), Some(max)) => max.sort_key(sort_key_size) >= from.sort_key(sort_key_size),
+                    _ => true
+                };
+                let starts_before_to = match (&to, partition.get_min_val()) {
+                    (Some(to), Some(min)) => min.sort_key(sort_key_size) <= to.sort_key(sort_key_size),
+                    _ => true
+                };
+                partition.is_active() && ends_after_from && starts_before_to
+            }).collect::<Vec<_>>())
+        }).await
+    }
+
+    async fn get_partitions_for_key_prefix(&self, index_id: u64, prefix: Row) -> Result<Vec<IdRow<Partition>>, CubeError> {
+        self.read_operation(move |db_ref| {
+            let index = IndexRocksTable::new(db_ref.clone()).get_row_or_not_found(index_id)?;
+            let sort_key_size = index.get_row().sort_key_size();
+            if prefix.len() as u64 > sort_key_size {
+                return Err(CubeError::user(format!(
+                    "Key prefix of {} values is longer than sort key of {} columns of index {}", prefix.len(), sort_key_size, index_id
+                )));
+            }
+            // Bounds are cut to the prefix length so a partition is kept if any key starting with the prefix falls into it
+            let prefix_size = prefix.len() as u64;
+            let partitions = PartitionRocksTable::new(db_ref).get_rows_by_index(
+                &PartitionIndexKey::ByIndexId(index_id),
+                &PartitionRocksIndex::IndexId
+            )?;
+            Ok(partitions.into_iter().filter(|r| {
+                let partition = r.get_row();
+                let ends_after_prefix = partition.get_max_val().as_ref()
+                    .map(|max| max.sort_key(prefix_size) >= prefix.sort_key(prefix_size))
+                    .unwrap_or(true);
+                let starts_before_prefix = partition.get_min_val().as_ref()
+                    .map(|min| min.sort_key(prefix_size) <= prefix.sort_key(prefix_size))
+                    .unwrap_or(true);
+                partition.is_active() && ends_after_prefix && starts_before_prefix
+            }).collect::<Vec<_>>())
+        }).await
+    }
+
+    async fn get_index_bounds(&self, index_id: u64) -> Result<(Option<Row>, Option<Row>), CubeError> {
+        self.read_operation(move |db_ref| {
+            let index = IndexRocksTable::new(db_ref.clone()).get_row_or_not_found(index_id)?;
+            let sort_key_size = index.get_row().sort_key_size();
+            let partitions = PartitionRocksTable::new(db_ref).get_rows_by_index(
+                &PartitionIndexKey::ByIndexId(index_id),
+                &PartitionRocksIndex::IndexId
+            )?.into_iter().filter(|r| r.get_row().is_active()).collect::<Vec<_>>();
+
+            let min = if partitions.iter().any(|p| p.get_row().get_min_val().is_none()) {
+                None
+            } else {
+                partitions.iter()
+                    .filter_map(|p| p.get_row().get_min_val().as_ref())
+                    .min_by(|a, b| a.sort_key(sort_key_size).cmp(&b.sort_key(sort_key_size)))
+                    .cloned()
+            };
+            let max = if partitions.iter().any(|p| p.get_row().get_max_val().is_none()) {
+                None
+            } else {
+                partitions.iter()
+                    .filter_map(|p| p.get_row().get_max_val().as_ref())
+                    .max_by(|a, b| a.sort_key(sort_key_size).cmp(&b.sort_key(sort_key_size)))
+                    .cloned()
+            };
+            Ok((min, max))
+        }).await
+    }
+
+    async fn find_partition_for_value(&self, index_id: u64, value: Row) -> Result<Option<IdRow<Partition>>, CubeError> {
+        self.read_operation(move |db_ref| {
+            let index = IndexRocksTable::new(db_ref.clone()).get_row_or_not_found(index_id)?;
+            let sort_key_size = index.get_row().sort_key_size();
+            let mut partitions = PartitionRocksTable::new(db_ref).get_rows_by_index(
+                &PartitionIndexKey::ByIndexId(index_id),
+                &PartitionRocksIndex::IndexId
+            )?.into_iter().filter(|r| r.get_row().is_active()).collect::<Vec<_>>();
+            sort_partitions_by_min_value(&mut partitions, sort_key_size);
+
+            let value_key = value.sort_key(sort_key_size);
+            let candidate = match partitions.binary_search_by(|p| match p.get_row().get_min_val() {
+                None => Ordering::Less,
+                Some(min) => min.sort_key(sort_key_size).cmp(&value_key)
+            }) {
+                Ok(pos) => pos,
+                Err(0) => return Ok(None),
+                Err(pos) => pos - 1
+            };
+
+            let partition = &partitions[candidate];
+            let fits_max = partition.get_row().get_max_val().as_ref()
+                .map(|max| value_key <= max.sort_key(sort_key_size))
+                .unwrap_or(true);
+            Ok(if fits_max { Some(partition.clone()) } else { None })
+        }).await
+    }
+
+    async fn check_partition_coverage(&self, index_id: u64) -> Result<Vec<CoverageIssue>, CubeError> {
+        self.read_operation(move |db_ref| {
+            let index = IndexRocksTable::new(db_ref.clone()).get_row_or_not_found(index_id)?;
+            let sort_key_size = index.get_row().sort_key_size();
+            let mut partitions = PartitionRocksTable::new(db_ref).get_rows_by_index(
+                &PartitionIndexKey::ByIndexId(index_id),
+                &PartitionRocksIndex::IndexId
+            )?.into_iter().filter(|r| r.get_row().is_active()).collect::<Vec<_>>();
+            sort_partitions_by_min_value(&mut partitions, sort_key_size);
+
+            let (first, last) = match (partitions.first(), partitions.last()) {
+                (Some(first), Some(last)) => (first, last),
+                _ => return Ok(vec![CoverageIssue::Gap { from: None, to: None }])
+            };
+            let mut issues = Vec::new();
+            if let Some(min) = first.get_row().get_min_val() {
+                issues.push(CoverageIssue::Gap { from: None, to: Some(min.clone()) });
+            }
+            // Adjacent partitions are expected to share a bound: max of one is min of the next
+            for pair in partitions.windows(2) {
+                let (prev, next) = (&pair[0], &pair[1]);
+                let ordering = match (prev.get_row().get_max_val(), next.get_row().get_min_val()) {
+                    (Some(max), Some(min)) => min.sort_key(sort_key_size).cmp(&max.sort_key(sort_key_size)),
+                    _ => Ordering::Less
+                };
+                match ordering {
+                    Ordering::Less => issues.push(CoverageIssue::Overlap {
+                        first_partition_id: prev.get_id(),
+                        second_partition_id: next.get_id()
+                    }),
+                    Ordering::Greater => issues.push(CoverageIssue::Gap {
+                        from: prev.get_row().get_max_val().clone(),
+                        to: next.get_row().get_min_val().clone()
+                    }),
+                    Ordering::Equal => {}
+                }
+            }
+            if let Some(max) = last.get_row().get_max_val() {
+                issues.push(CoverageIssue::Gap { from: Some(max.clone()), to: None });
+            }
+            Ok(issues)
+        }).await
+    }
+
     async fn create_chunk(&self, partition_id: u64, row_count: usize) -> Result<IdRow<Chunk>, CubeError> {
         self.write_operation(move |db_ref, batch_pipe| {
             let rocks_chunk = ChunkRocksTable::new(db_ref.clone());
@@ -1712,6 +3550,51 @@ impl MetaStore for RocksMetaStore {
         }).await
     }
 
+    async fn get_chunks_by_partitions(&self, partition_ids: Vec<u64>) -> Result<HashMap<u64, Vec<IdRow<Chunk>>>, CubeError> {
+        self.read_operation(move |db_ref| {
+            let table = ChunkRocksTable::new(db_ref);
+            let mut result = HashMap::new();
+            for partition_id in partition_ids.into_iter() {
+                let chunks = table.get_rows_by_index(
+                    &ChunkIndexKey::ByPartitionId(partition_id),
+                    &ChunkRocksIndex::PartitionId
+                )?.into_iter().filter(|c| c.get_row().uploaded() && c.get_row().active()).collect::<Vec<_>>();
+                result.insert(partition_id, chunks);
+            }
+            Ok(result)
+        }).await
+    }
+
+    async fn get_referenced_remote_files(&self) -> Result<HashSet<String>, CubeError> {
+        self.read_operation(|db_ref| {
+            let mut files = HashSet::new();
+            for partition in PartitionRocksTable::new(db_ref.clone()).all_rows()?.into_iter() {
+                if partition.get_row().is_active() {
+                    files.extend(partition.get_row().get_full_name(partition.get_id()));
+                }
+            }
+            for chunk in ChunkRocksTable::new(db_ref).all_rows()?.into_iter() {
+                if chunk.get_row().active() && chunk.get_row().uploaded() {
+                    files.insert(chunk.get_row().get_full_name(chunk.get_id()));
+                }
+            }
+            Ok(files)
+        }).await
+    }
+
+    async fn get_oldest_chunks_by_partition(&self, partition_id: u64, limit: usize) -> Result<Vec<IdRow<Chunk>>, CubeError> {
+        self.read_operation(move |db_ref| {
+            let table = ChunkRocksTable::new(db_ref);
+            let mut chunks = table.get_rows_by_index(
+                &ChunkIndexKey::ByPartitionId(partition_id),
+                &ChunkRocksIndex::PartitionId
+            )?.into_iter().filter(|c| c.get_row().uploaded() && c.get_row().active()).collect::<Vec<_>>();
+            chunks.sort_by_key(|c| c.get_id());
+            chunks.truncate(limit);
+            Ok(chunks)
+        }).await
+    }
+
     async fn chunk_uploaded(&self, chunk_id: u64) -> Result<IdRow<Chunk>, CubeError> {
         self.write_operation(move |db_ref, batch_pipe| {
             let table = ChunkRocksTable::new(db_ref.clone());
@@ -1759,6 +3642,18 @@ impl MetaStore for RocksMetaStore {
         }).await
     }
 
+    async fn get_all_wals(&self) -> Result<Vec<IdRow<WAL>>, CubeError> {
+        self.read_operation(|db_ref| {
+            WALRocksTable::new(db_ref).all_rows()
+        }).await
+    }
+
+    async fn get_all_wals_pending_upload(&self) -> Result<Vec<IdRow<WAL>>, CubeError> {
+        self.read_operation(|db_ref| {
+            Ok(WALRocksTable::new(db_ref).all_rows()?.into_iter().filter(|w| !w.get_row().uploaded()).collect::<Vec<_>>())
+        }).await
+    }
+
     async fn delete_wal(&self, wal_id: u64) -> Result<(), CubeError> {
         self.write_operation(move |db_ref, batch_pipe| {
             WALRocksTable::new(db_ref.clone()).delete(wal_id, batch_pipe)?;
@@ -1776,6 +3671,74 @@ impl MetaStore for RocksMetaStore {
         }).await
     }
 
+    async fn wal_flushed(&self, wal_id: u64, new_chunks: Vec<(u64, usize)>) -> Result<Vec<IdRow<Chunk>>, CubeError> {
+        self.write_operation(move |db_ref, batch_pipe| {
+            let wal_table = WALRocksTable::new(db_ref.clone());
+            let partition_table = PartitionRocksTable::new(db_ref.clone());
+            let chunk_table = ChunkRocksTable::new(db_ref.clone());
+
+            let mut chunks = Vec::with_capacity(new_chunks.len());
+            for (partition_id, row_count) in new_chunks.into_iter() {
+                partition_table.get_row_or_not_found(partition_id)?;
+                chunks.push(chunk_table.insert(Chunk::new(partition_id, row_count), batch_pipe)?);
+            }
+
+            wal_table.update_with_fn(wal_id, |row| row.set_uploaded(true), batch_pipe)?;
+
+            Ok(chunks)
+        }).await
+    }
+
+
+    async fn get_jobs_for_table(&self, table_id: u64) -> Result<Vec<IdRow<Job>>, CubeError> {
+        self.read_operation(move |db_ref| {
+            let jobs_table = JobRocksTable::new(db_ref.clone());
+            let indexes_table = IndexRocksTable::new(db_ref.clone());
+            let partitions_table = PartitionRocksTable::new(db_ref.clone());
+            let wals_table = WALRocksTable::new(db_ref);
+
+            let mut references = vec![(RowKey::Table(TableId::Tables, table_id), JobType::TableImport)];
+            for wal in wals_table.get_rows_by_index(&WALIndexKey::ByTable(table_id), &WALRocksIndex::TableID)?.into_iter() {
+                references.push((RowKey::Table(TableId::WALs, wal.get_id()), JobType::WalPartitioning));
+            }
+            let indexes = indexes_table.get_rows_by_index(&IndexIndexKey::TableId(table_id), &IndexRocksIndex::TableID)?;
+            for index in indexes.into_iter() {
+                let partitions = partitions_table.get_rows_by_index(&PartitionIndexKey::ByIndexId(index.get_id()), &PartitionRocksIndex::IndexId)?;
+                for partition in partitions.into_iter() {
+                    references.push((RowKey::Table(TableId::Partitions, partition.get_id()), JobType::PartitionCompaction));
+                    references.push((RowKey::Table(TableId::Partitions, partition.get_id()), JobType::Repartition));
+                }
+            }
+
+            let mut jobs = Vec::new();
+            for (row_reference, job_type) in references.into_iter() {
+                jobs.extend(jobs_table.get_rows_by_index(&JobIndexKey::RowReference(row_reference, job_type), &JobRocksIndex::RowReference)?);
+            }
+            Ok(jobs)
+        }).await
+    }
+
+    async fn wait_for_table_jobs(&self, table_id: u64, timeout: Duration) -> Result<(), CubeError> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let pending = self.get_jobs_for_table(table_id).await?.into_iter().filter(|j| match j.get_row().status() {
+                JobStatus::Scheduled(_) | JobStatus::ProcessingBy(_) => true,
+                _ => false
+            }).collect::<Vec<_>>();
+            if pending.is_empty() {
+                return Ok(());
+            }
+            if Instant::now() >= deadline {
+                return Err(CubeError::internal(format!(
+                    "Timeout of {:?} exceeded while waiting for jobs of table {}: {:?}",
+                    timeout,
+                    table_id,
+                    pending
+                )));
+            }
+            tokio::time::delay_for(Duration::from_millis(100)).await;
+        }
+    }
 
     async fn add_job(&self, job: Job) -> Result<Option<IdRow<Job>>, CubeError> {
         self.write_operation(move |db_ref, batch_pipe| {
@@ -1832,11 +3795,21 @@ impl MetaStore for RocksMetaStore {
         self.write_operation(move |db_ref, batch_pipe| {
             Ok(
                 JobRocksTable::new(db_ref)
-                    .update_with_fn(job_id, |row| row.update_heart_beat(), batch_pipe)?
+                    .update_unindexed_with_fn(job_id, |row| row.update_heart_beat(), batch_pipe)?
             )
         }).await
     }
 
+    async fn update_heart_beats(&self, job_ids: Vec<u64>) -> Result<(), CubeError> {
+        self.write_operation(move |db_ref, batch_pipe| {
+            let jobs_table = JobRocksTable::new(db_ref);
+            for job_id in job_ids {
+                jobs_table.update_unindexed_with_fn(job_id, |row| row.update_heart_beat(), batch_pipe)?;
+            }
+            Ok(())
+        }).await
+    }
+
     async fn update_status(&self, job_id: u64, status: JobStatus) -> Result<IdRow<Job>, CubeError> {
         self.write_operation(move |db_ref, batch_pipe| {
             Ok(
@@ -1845,15 +3818,34 @@ impl MetaStore for RocksMetaStore {
             )
         }).await
     }
+
+    async fn find_orphaned_jobs(&self) -> Result<Vec<IdRow<Job>>, CubeError> {
+        let now = DateTime::<Utc>::from(self.config.clock.now());
+        let config = self.config.clone();
+        self.read_operation(move |db_ref| {
+            let mut orphaned = Vec::new();
+            for job in JobRocksTable::new(db_ref).all_rows()?.into_iter() {
+                if job.get_row().is_orphaned(now, config.heart_beat_timeout(job.get_row().job_type()))? {
+                    orphaned.push(job);
+                }
+            }
+            Ok(orphaned)
+        }).await
+    }
 }
 
 
 #[cfg(test)]
 mod tests {
     use super::*;
+
     use crate::remotefs::LocalDirRemoteFs;
     use std::{env, fs};
     use crate::config::Config;
+    use crate::table::TimestampValue;
+    use parquet::file::reader::{SerializedFileReader, FileReader};
+    use crate::CubeErrorCause;
+    use crate::metastore::clock::TestClock;
 
     #[test]
     fn macro_test() {
@@ -1871,7 +3863,7 @@ mod tests {
         let remote_fs = LocalDirRemoteFs::new(store_path.clone(), remote_store_path.clone());
 
         {
-            let meta_store = RocksMetaStore::new(store_path.join("metastore").as_path(), remote_fs);
+            let meta_store = RocksMetaStore::new(store_path.join("metastore").as_path(), remote_fs).unwrap();
 
             let schema_1 = meta_store.create_schema("foo".to_string(), false).await.unwrap();
             println!("New id: {}", schema_1.id);
@@ -1940,7 +3932,7 @@ mod tests {
         let _ = fs::remove_dir_all(remote_store_path.clone());
         let remote_fs = LocalDirRemoteFs::new(store_path.clone(), remote_store_path.clone());
         {
-            let meta_store = RocksMetaStore::new(store_path.clone().join("metastore").as_path(), remote_fs);
+            let meta_store = RocksMetaStore::new(store_path.clone().join("metastore").as_path(), remote_fs).unwrap();
 
             let schema_1 = meta_store.create_schema( "foo".to_string(), false).await.unwrap();
             let mut columns =  Vec::new();
@@ -1948,11 +3940,11 @@ mod tests {
             columns.push(Column::new("col2".to_string(), ColumnType::String, 1));
             columns.push(Column::new("col3".to_string(), ColumnType::Decimal, 2));
 
-            let table1 = meta_store.create_table("foo".to_string(), "boo".to_string(), columns.clone(), None, None, vec![]).await.unwrap();
+            let table1 = meta_store.create_table("foo".to_string(), "boo".to_string(), columns.clone(), None, None, vec![], vec![]).await.unwrap();
             let table1_id = table1.id;
 
             assert!(schema_1.id == table1.get_row().get_schema_id());
-            assert!(meta_store.create_table("foo".to_string(), "boo".to_string(), columns.clone(), None, None, vec![]).await.is_err());
+            assert!(meta_store.create_table("foo".to_string(), "boo".to_string(), columns.clone(), None, None, vec![], vec![]).await.is_err());
 
             assert_eq!(meta_store.get_table("foo".to_string(), "boo".to_string()).await.unwrap(), table1);
 
@@ -1965,6 +3957,2459 @@ mod tests {
         let _ = fs::remove_dir_all(remote_store_path.clone());
     }
 
+    #[actix_rt::test]
+    async fn compaction_input_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("compaction_input_test");
+        {
+            meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            let columns = vec![Column::new("col1".to_string(), ColumnType::Int, 0)];
+            let table = meta_store.create_table("foo".to_string(), "boo".to_string(), columns, None, None, vec![], vec![]).await.unwrap();
+            let index = meta_store.get_default_index(table.get_id()).await.unwrap();
+            let partition = meta_store.get_active_partitions_by_index_id(index.get_id()).await.unwrap()[0].clone();
+
+            let chunk_1 = meta_store.create_chunk(partition.get_id(), 10).await.unwrap();
+            meta_store.chunk_uploaded(chunk_1.get_id()).await.unwrap();
+            let chunk_2 = meta_store.create_chunk(partition.get_id(), 15).await.unwrap();
+            meta_store.chunk_uploaded(chunk_2.get_id()).await.unwrap();
+            // not uploaded yet so shouldn't be compacted
+            meta_store.create_chunk(partition.get_id(), 5).await.unwrap();
+
+            let (p, i, chunks) = match meta_store.get_compaction_input(partition.get_id(), None).await.unwrap() {
+                CompactionInput::Compact(p, i, chunks) => (p, i, chunks),
+                x => panic!("Unexpected compaction input: {:?}", x)
+            };
+            assert_eq!(p, partition);
+            assert_eq!(i, index);
+            assert_eq!(chunks.iter().map(|c| c.get_id()).collect::<Vec<_>>(), vec![chunk_1.get_id(), chunk_2.get_id()]);
+
+            assert_eq!(
+                meta_store.get_compaction_input(partition.get_id(), Some(26)).await.unwrap(),
+                CompactionInput::NotWorthCompacting { chunks_row_count: 25 }
+            );
+            match meta_store.get_compaction_input(partition.get_id(), Some(25)).await.unwrap() {
+                CompactionInput::Compact(_, _, chunks) => assert_eq!(chunks.len(), 2),
+                x => panic!("Unexpected compaction input: {:?}", x)
+            };
+
+            let child = meta_store.create_partition(partition.get_row().child(partition.get_id())).await.unwrap();
+            meta_store.swap_active_partitions(
+                vec![partition.get_id()],
+                vec![child.get_id()],
+                vec![],
+                vec![(0, (None, None))],
+                None,
+                None
+            ).await.unwrap();
+            assert!(meta_store.get_compaction_input(partition.get_id(), None).await.is_err());
+        }
+        RocksMetaStore::cleanup_test_metastore("compaction_input_test");
+    }
+
+    #[actix_rt::test]
+    async fn wal_flushed_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("wal_flushed_test");
+        {
+            meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            let columns = vec![Column::new("col1".to_string(), ColumnType::Int, 0)];
+            let table = meta_store.create_table("foo".to_string(), "boo".to_string(), columns, None, None, vec![], vec![]).await.unwrap();
+            let index = meta_store.get_default_index(table.get_id()).await.unwrap();
+            let partition = meta_store.get_active_partitions_by_index_id(index.get_id()).await.unwrap()[0].clone();
+
+            let wal_1 = meta_store.create_wal(table.get_id(), 25).await.unwrap();
+            let chunks = meta_store.wal_flushed(wal_1.get_id(), vec![(partition.get_id(), 10), (partition.get_id(), 15)]).await.unwrap();
+            assert_eq!(chunks.len(), 2);
+            for chunk in chunks.iter() {
+                assert_eq!(&meta_store.get_chunk(chunk.get_id()).await.unwrap(), chunk);
+            }
+            assert!(meta_store.get_wal(wal_1.get_id()).await.unwrap().get_row().uploaded());
+
+            // Second chunk references missing partition so whole operation should be rolled back
+            let wal_2 = meta_store.create_wal(table.get_id(), 20).await.unwrap();
+            assert!(meta_store.wal_flushed(wal_2.get_id(), vec![(partition.get_id(), 10), (100, 10)]).await.is_err());
+            assert!(!meta_store.get_wal(wal_2.get_id()).await.unwrap().get_row().uploaded());
+            assert_eq!(meta_store.chunks_table().all_rows().await.unwrap().len(), 2);
+        }
+        RocksMetaStore::cleanup_test_metastore("wal_flushed_test");
+    }
+
+    #[actix_rt::test]
+    async fn drop_table_coalesced_events_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("drop_table_coalesced_events_test");
+        {
+            let (sender, mut receiver) = tokio::sync::broadcast::channel(10000);
+            meta_store.add_listener(sender).await;
+            meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            let columns = vec![Column::new("col1".to_string(), ColumnType::Int, 0)];
+            let table = meta_store.create_table("foo".to_string(), "boo".to_string(), columns, None, None, vec![], vec![]).await.unwrap();
+            let index = meta_store.get_default_index(table.get_id()).await.unwrap();
+            let partition = meta_store.get_active_partitions_by_index_id(index.get_id()).await.unwrap()[0].clone();
+            for _ in 0..500 {
+                meta_store.create_chunk(partition.get_id(), 10).await.unwrap();
+            }
+            while receiver.try_recv().is_ok() {}
+
+            meta_store.drop_table(table.get_id(), true).await.unwrap();
+
+            let mut events = Vec::new();
+            while let Ok(event) = receiver.try_recv() {
+                events.push(event);
+            }
+            assert_eq!(events.len(), 5);
+            assert!(events.iter().any(|e| if let MetaStoreEvent::DeleteTable(t) = e { t.get_id() == table.get_id() } else { false }));
+            assert!(events.iter().any(|e| if let MetaStoreEvent::DeleteCoalesced(TableId::Chunks, 500) = e { true } else { false }));
+            assert!(meta_store.chunks_table().all_rows().await.unwrap().is_empty());
+        }
+        RocksMetaStore::cleanup_test_metastore("drop_table_coalesced_events_test");
+    }
+
+    #[actix_rt::test]
+    async fn index_bounds_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("index_bounds_test");
+        {
+            meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            let columns = vec![Column::new("col1".to_string(), ColumnType::Int, 0)];
+            let table = meta_store.create_table("foo".to_string(), "boo".to_string(), columns, None, None, vec![], vec![]).await.unwrap();
+            let index = meta_store.get_default_index(table.get_id()).await.unwrap();
+            let partition = meta_store.get_active_partitions_by_index_id(index.get_id()).await.unwrap()[0].clone();
+            assert_eq!(meta_store.get_index_bounds(index.get_id()).await.unwrap(), (None, None));
+
+            let row = |v: i64| Row::new(vec![TableValue::Int(v)]);
+            let mut children = Vec::new();
+            for _ in 0..3 {
+                children.push(meta_store.create_partition(partition.get_row().child(partition.get_id())).await.unwrap().get_id());
+            }
+            meta_store.swap_active_partitions(
+                vec![partition.get_id()],
+                children,
+                vec![],
+                vec![
+                    (0, (Some(row(20)), Some(row(29)))),
+                    (0, (Some(row(5)), Some(row(19)))),
+                    (0, (Some(row(30)), Some(row(42)))),
+                ],
+                None,
+                None
+            ).await.unwrap();
+            assert_eq!(meta_store.get_index_bounds(index.get_id()).await.unwrap(), (Some(row(5)), Some(row(42))));
+
+            meta_store.create_partition(Partition::new(index.get_id(), None, Some(row(4)))).await.unwrap();
+            assert_eq!(meta_store.get_index_bounds(index.get_id()).await.unwrap(), (None, Some(row(42))));
+        }
+        RocksMetaStore::cleanup_test_metastore("index_bounds_test");
+    }
+
+    #[actix_rt::test]
+    async fn row_exists_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("row_exists_test");
+        {
+            let schema = meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            assert!(meta_store.schemas_table().row_exists(schema.get_id()).await.unwrap());
+            assert!(!meta_store.schemas_table().row_exists(schema.get_id() + 1).await.unwrap());
+            assert!(!meta_store.tables_table().row_exists(schema.get_id()).await.unwrap());
+
+            meta_store.delete_schema_by_id(schema.get_id()).await.unwrap();
+            assert!(!meta_store.schemas_table().row_exists(schema.get_id()).await.unwrap());
+        }
+        RocksMetaStore::cleanup_test_metastore("row_exists_test");
+    }
+
+    #[actix_rt::test]
+    async fn insert_rows_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("insert_rows_test");
+        {
+            let (sender, mut receiver) = tokio::sync::broadcast::channel(100);
+            meta_store.add_listener(sender).await;
+
+            let schemas = meta_store.schemas_table();
+            let inserted = schemas.insert_rows(vec![
+                Schema { name: "foo".to_string() },
+                Schema { name: "bar".to_string() },
+                Schema { name: "baz".to_string() }
+            ]).await.unwrap();
+            assert_eq!(inserted.iter().map(|s| s.get_row().get_name().as_str()).collect::<Vec<_>>(), vec!["foo", "bar", "baz"]);
+            assert_eq!(inserted.iter().map(|s| s.get_id()).collect::<HashSet<_>>().len(), 3);
+            for schema in inserted.iter() {
+                assert_eq!(&meta_store.get_schema(schema.get_row().get_name().clone()).await.unwrap(), schema);
+                match receiver.recv().await.unwrap() {
+                    MetaStoreEvent::Insert(TableId::Schemas, id) => assert_eq!(id, schema.get_id()),
+                    e => panic!("Unexpected event: {:?}", e)
+                }
+            }
+
+            let err = schemas.insert_rows(vec![Schema { name: "qux".to_string() }, Schema { name: "foo".to_string() }]).await.unwrap_err();
+            assert_eq!(err.cause(), CubeErrorCause::UniqueViolation);
+            let err = schemas.insert_rows(vec![Schema { name: "qux".to_string() }, Schema { name: "qux".to_string() }]).await.unwrap_err();
+            assert_eq!(err.cause(), CubeErrorCause::UniqueViolation);
+            assert_eq!(meta_store.get_schemas().await.unwrap().len(), 3);
+        }
+        RocksMetaStore::cleanup_test_metastore("insert_rows_test");
+    }
+
+    #[actix_rt::test]
+    async fn subscribe_with_snapshot_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("subscribe_with_snapshot_test");
+        {
+            let mut existing = Vec::new();
+            for name in vec!["foo", "bar", "baz"].into_iter() {
+                existing.push(meta_store.create_schema(name.to_string(), false).await.unwrap().get_id());
+            }
+
+            let (sender, mut receiver) = tokio::sync::broadcast::channel(100);
+            let writer = {
+                let meta_store = meta_store.clone();
+                tokio::spawn(async move { meta_store.create_schema("qux".to_string(), false).await.unwrap().get_id() })
+            };
+            meta_store.subscribe_with_snapshot(sender).await.unwrap();
+            let concurrent = writer.await.unwrap();
+            let live = meta_store.create_schema("quux".to_string(), false).await.unwrap().get_id();
+
+            let mut received = Vec::new();
+            while let Ok(event) = receiver.try_recv() {
+                match event {
+                    MetaStoreEvent::Insert(TableId::Schemas, id) => received.push(id),
+                    e => panic!("Unexpected event: {:?}", e)
+                }
+            }
+            // The concurrent write lands either in the snapshot or as a live event but exactly once
+            let mut expected = existing.clone();
+            expected.push(concurrent);
+            expected.push(live);
+            assert_eq!(received.iter().cloned().collect::<HashSet<_>>(), expected.iter().cloned().collect::<HashSet<_>>());
+            assert_eq!(received.len(), expected.len());
+            assert_eq!(&received[0..3], existing.as_slice());
+            assert_eq!(received.last(), Some(&live));
+            assert_eq!(meta_store.listener_dropped_counts().await, vec![0]);
+        }
+        RocksMetaStore::cleanup_test_metastore("subscribe_with_snapshot_test");
+    }
+
+    #[actix_rt::test]
+    async fn add_column_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("add_column_test");
+        {
+            meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            let columns = vec![Column::new("col1".to_string(), ColumnType::Int, 0), Column::new("col2".to_string(), ColumnType::Int, 1)];
+            let table = meta_store.create_table(
+                "foo".to_string(), "boo".to_string(), columns, None, None,
+                vec![IndexDef { name: "by_col2".to_string(), columns: vec!["col2".to_string()] }],
+                vec![]
+            ).await.unwrap();
+            let partitions_before = meta_store.get_all_partitions(false).await.unwrap();
+
+            meta_store.add_column(table.get_id(), Column::new("col3".to_string(), ColumnType::String, 0)).await.unwrap();
+
+            let table = meta_store.get_table_by_id(table.get_id()).await.unwrap();
+            assert_eq!(table.get_row().get_columns(), &vec![
+                Column::new("col1".to_string(), ColumnType::Int, 0),
+                Column::new("col2".to_string(), ColumnType::Int, 1),
+                Column::new("col3".to_string(), ColumnType::String, 2)
+            ]);
+            let default_index = meta_store.get_default_index(table.get_id()).await.unwrap();
+            assert_eq!(default_index.get_row().sort_key_size(), 2);
+            assert_eq!(default_index.get_row().columns()[2], Column::new("col3".to_string(), ColumnType::String, 2));
+            let by_col2 = meta_store.get_table_indexes(table.get_id()).await.unwrap()
+                .into_iter().find(|i| i.get_row().get_name() == "by_col2").unwrap();
+            assert_eq!(by_col2.get_row().sort_key_size(), 1);
+            assert_eq!(
+                by_col2.get_row().columns().iter().map(|c| c.get_name().as_str()).collect::<Vec<_>>(),
+                vec!["col2", "col1", "col3"]
+            );
+            assert_eq!(by_col2.get_row().columns()[2].get_index(), 2);
+            assert_eq!(meta_store.get_all_partitions(false).await.unwrap(), partitions_before);
+
+            let err = meta_store.add_column(table.get_id(), Column::new("col2".to_string(), ColumnType::String, 0)).await.unwrap_err();
+            assert_eq!(err.cause(), CubeErrorCause::User);
+            assert_eq!(meta_store.get_table_by_id(table.get_id()).await.unwrap().get_row().get_columns().len(), 3);
+        }
+        RocksMetaStore::cleanup_test_metastore("add_column_test");
+    }
+
+    #[actix_rt::test]
+    async fn get_tables_with_path_for_schema_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("get_tables_with_path_for_schema_test");
+        {
+            let foo = meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            let bar = meta_store.create_schema("bar".to_string(), false).await.unwrap();
+            let columns = vec![Column::new("col1".to_string(), ColumnType::Int, 0)];
+            let foo_table1 = meta_store.create_table("foo".to_string(), "t1".to_string(), columns.clone(), None, None, vec![], vec![]).await.unwrap();
+            meta_store.create_table("bar".to_string(), "t2".to_string(), columns.clone(), None, None, vec![], vec![]).await.unwrap();
+            let foo_table3 = meta_store.create_table("foo".to_string(), "t3".to_string(), columns.clone(), None, None, vec![], vec![]).await.unwrap();
+
+            let paths = meta_store.get_tables_with_path_for_schema(foo.get_id()).await.unwrap();
+            assert_eq!(paths.iter().map(|p| p.table.clone()).collect::<Vec<_>>(), vec![foo_table1, foo_table3]);
+            assert!(paths.iter().all(|p| p.schema.as_ref() == &foo));
+
+            assert_eq!(meta_store.get_tables_with_path_for_schema(bar.get_id()).await.unwrap().len(), 1);
+            assert!(meta_store.get_tables_with_path_for_schema(bar.get_id() + 100).await.unwrap().is_empty());
+        }
+        RocksMetaStore::cleanup_test_metastore("get_tables_with_path_for_schema_test");
+    }
+
+    #[actix_rt::test]
+    async fn table_schema_index_backfill_test() {
+        let (remote_fs, meta_store) = RocksMetaStore::prepare_test_metastore("table_schema_index_backfill_test");
+        {
+            let foo = meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            let columns = vec![Column::new("col1".to_string(), ColumnType::Int, 0)];
+            let t1 = meta_store.create_table("foo".to_string(), "t1".to_string(), columns.clone(), None, None, vec![], vec![]).await.unwrap();
+            let t2 = meta_store.create_table("foo".to_string(), "t2".to_string(), columns, None, None, vec![], vec![]).await.unwrap();
+
+            // Strip the schema id index to get the layout written before it existed
+            let path = {
+                let db = meta_store.db.read().await.clone();
+                let tables = TableRocksTable::new(db.clone());
+                let index = &TableRocksIndex::SchemaId;
+                for table in vec![&t1, &t2] {
+                    let key = RowKey::SecondaryIndex(
+                        tables.index_id(BaseRocksSecondaryIndex::<Table>::get_id(index)),
+                        index.key_prefix(table.get_row()),
+                        table.get_id()
+                    );
+                    db.delete(key.to_bytes()).unwrap();
+                }
+                // Metastores marked before the schema id index was added hold version 1
+                db.put(migration_version_key(), 1u64.to_be_bytes()).unwrap();
+                db.path().to_path_buf()
+            };
+            assert!(meta_store.get_tables_with_path_for_schema(foo.get_id()).await.unwrap().is_empty());
+            drop(meta_store);
+
+            let meta_store = RocksMetaStore::new(path.clone(), remote_fs.clone()).unwrap();
+            let paths = meta_store.get_tables_with_path_for_schema(foo.get_id()).await.unwrap();
+            assert_eq!(paths.into_iter().map(|p| p.table).collect::<Vec<_>>(), vec![t1.clone(), t2]);
+            assert!(meta_store.check_integrity().await.unwrap().is_empty());
+
+            // Once marked as done the backfill isn't repeated on the next open
+            let db = meta_store.db.read().await.clone();
+            assert_eq!(RocksMetaStore::migration_version(&db).unwrap(), METASTORE_MIGRATION_VERSION);
+            let tables = TableRocksTable::new(db.clone());
+            let index = &TableRocksIndex::SchemaId;
+            db.delete(RowKey::SecondaryIndex(
+                tables.index_id(BaseRocksSecondaryIndex::<Table>::get_id(index)),
+                index.key_prefix(t1.get_row()),
+                t1.get_id()
+            ).to_bytes()).unwrap();
+            drop(tables);
+            drop(db);
+            drop(meta_store);
+
+            let meta_store = RocksMetaStore::new(path, remote_fs).unwrap();
+            assert_eq!(meta_store.get_tables_with_path_for_schema(foo.get_id()).await.unwrap().len(), 1);
+        }
+        RocksMetaStore::cleanup_test_metastore("table_schema_index_backfill_test");
+    }
+
+    #[actix_rt::test]
+    async fn schema_renamed_event_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("schema_renamed_event_test");
+        {
+            let schema = meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            let (sender, mut receiver) = tokio::sync::broadcast::channel(100);
+            meta_store.add_listener(sender).await;
+
+            meta_store.rename_schema("foo".to_string(), "bar".to_string()).await.unwrap();
+            meta_store.rename_schema_by_id(schema.get_id(), "baz".to_string()).await.unwrap();
+
+            let mut renames = Vec::new();
+            while let Ok(event) = receiver.try_recv() {
+                match event {
+                    MetaStoreEvent::SchemaRenamed { id, old_name, new_name } => renames.push((id, old_name, new_name)),
+                    e => panic!("Unexpected event: {:?}", e)
+                }
+            }
+            assert_eq!(renames, vec![
+                (schema.get_id(), "foo".to_string(), "bar".to_string()),
+                (schema.get_id(), "bar".to_string(), "baz".to_string())
+            ]);
+        }
+        RocksMetaStore::cleanup_test_metastore("schema_renamed_event_test");
+    }
+
+    #[actix_rt::test]
+    async fn table_initial_partitions_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("table_initial_partitions_test");
+        {
+            meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            let columns = vec![Column::new("day".to_string(), ColumnType::Timestamp, 0)];
+            let day = |d: i64| Some(Row::new(vec![TableValue::Timestamp(TimestampValue::new(d * 24 * 3600 * 1_000_000_000))]));
+            let bounds = vec![
+                (None, day(1)),
+                (day(1), day(2)),
+                (day(2), None),
+            ];
+            let table = meta_store.create_table("foo".to_string(), "boo".to_string(), columns, None, None, vec![], bounds.clone()).await.unwrap();
+            let index = meta_store.get_default_index(table.get_id()).await.unwrap();
+            let partitions = meta_store.get_active_partitions_by_index_id(index.get_id()).await.unwrap();
+            assert_eq!(
+                partitions.iter().map(|p| (p.get_row().get_min_val().clone(), p.get_row().get_max_val().clone())).collect::<Vec<_>>(),
+                bounds
+            );
+        }
+        RocksMetaStore::cleanup_test_metastore("table_initial_partitions_test");
+    }
+
+    #[actix_rt::test]
+    async fn export_catalog_parquet_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("export_catalog_parquet_test");
+        let export_path = env::current_dir().unwrap().join("test-export_catalog_parquet_test-export");
+        let _ = fs::remove_dir_all(export_path.clone());
+        {
+            meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            meta_store.create_schema("bar".to_string(), false).await.unwrap();
+            let columns = vec![Column::new("col1".to_string(), ColumnType::Int, 0)];
+            let table = meta_store.create_table("foo".to_string(), "boo".to_string(), columns, None, None, vec![], vec![]).await.unwrap();
+            let index = meta_store.get_default_index(table.get_id()).await.unwrap();
+            let partition = meta_store.get_active_partitions_by_index_id(index.get_id()).await.unwrap()[0].clone();
+            for _ in 0..3 {
+                meta_store.create_chunk(partition.get_id(), 10).await.unwrap();
+            }
+
+            meta_store.export_catalog_parquet(export_path.to_str().unwrap()).await.unwrap();
+
+            let row_count = |name: &str| {
+                let file = std::fs::File::open(export_path.join(format!("{}.parquet", name))).unwrap();
+                let reader = SerializedFileReader::new(file).unwrap();
+                reader.metadata().file_metadata().num_rows()
+            };
+            assert_eq!(row_count("schemas"), 2);
+            assert_eq!(row_count("tables"), 1);
+            assert_eq!(row_count("chunks"), 3);
+        }
+        let _ = fs::remove_dir_all(export_path);
+        RocksMetaStore::cleanup_test_metastore("export_catalog_parquet_test");
+    }
+
+    #[actix_rt::test]
+    async fn partitions_for_key_prefix_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("partitions_for_key_prefix_test");
+        {
+            meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            let columns = vec![
+                Column::new("customer".to_string(), ColumnType::String, 0),
+                Column::new("day".to_string(), ColumnType::Int, 1),
+            ];
+            let row = |c: &str, d: i64| Some(Row::new(vec![TableValue::String(c.to_string()), TableValue::Int(d)]));
+            let table = meta_store.create_table(
+                "foo".to_string(), "boo".to_string(), columns, None, None, vec![],
+                vec![
+                    (None, row("b", 0)),
+                    (row("b", 0), row("b", 10)),
+                    (row("b", 10), row("d", 0)),
+                    (row("d", 0), None),
+                ]
+            ).await.unwrap();
+            let index = meta_store.get_default_index(table.get_id()).await.unwrap();
+            let partitions = meta_store.get_active_partitions_by_index_id(index.get_id()).await.unwrap();
+            let ids = |p: Vec<IdRow<Partition>>| p.into_iter().map(|p| p.get_id()).collect::<Vec<_>>();
+            let prefix = |c: &str| Row::new(vec![TableValue::String(c.to_string())]);
+
+            let a = meta_store.get_partitions_for_key_prefix(index.get_id(), prefix("a")).await.unwrap();
+            assert_eq!(ids(a), vec![partitions[0].get_id()]);
+            let b = meta_store.get_partitions_for_key_prefix(index.get_id(), prefix("b")).await.unwrap();
+            assert_eq!(ids(b), vec![partitions[0].get_id(), partitions[1].get_id(), partitions[2].get_id()]);
+            let c = meta_store.get_partitions_for_key_prefix(index.get_id(), prefix("c")).await.unwrap();
+            assert_eq!(ids(c), vec![partitions[2].get_id()]);
+            let e = meta_store.get_partitions_for_key_prefix(index.get_id(), prefix("e")).await.unwrap();
+            assert_eq!(ids(e), vec![partitions[3].get_id()]);
+
+            let full_key = Row::new(vec![TableValue::String("b".to_string()), TableValue::Int(5)]);
+            let b5 = meta_store.get_partitions_for_key_prefix(index.get_id(), full_key).await.unwrap();
+            assert_eq!(ids(b5), vec![partitions[1].get_id()]);
+            let all = meta_store.get_partitions_for_key_prefix(index.get_id(), Row::new(vec![])).await.unwrap();
+            assert_eq!(all.len(), 4);
+
+            let too_long = Row::new(vec![TableValue::String("b".to_string()), TableValue::Int(5), TableValue::Int(1)]);
+            let err = meta_store.get_partitions_for_key_prefix(index.get_id(), too_long).await.unwrap_err();
+            assert_eq!(err.cause(), CubeErrorCause::User);
+        }
+        RocksMetaStore::cleanup_test_metastore("partitions_for_key_prefix_test");
+    }
+
+    #[actix_rt::test]
+    async fn check_partition_coverage_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("check_partition_coverage_test");
+        {
+            meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            let columns = vec![Column::new("col1".to_string(), ColumnType::Int, 0)];
+            let row = |v: i64| Row::new(vec![TableValue::Int(v)]);
+            let covered = meta_store.create_table(
+                "foo".to_string(), "covered".to_string(), columns.clone(), None, None, vec![],
+                vec![(Some(row(10)), None), (None, Some(row(10)))]
+            ).await.unwrap();
+            let index = meta_store.get_default_index(covered.get_id()).await.unwrap();
+            assert_eq!(meta_store.check_partition_coverage(index.get_id()).await.unwrap(), vec![]);
+
+            let broken = meta_store.create_table(
+                "foo".to_string(), "broken".to_string(), columns, None, None, vec![],
+                vec![
+                    (Some(row(35)), Some(row(40))),
+                    (Some(row(2)), Some(row(20))),
+                    (Some(row(15)), Some(row(30))),
+                ]
+            ).await.unwrap();
+            let index = meta_store.get_default_index(broken.get_id()).await.unwrap();
+            let partitions = meta_store.get_active_partitions_by_index_id(index.get_id()).await.unwrap();
+            assert_eq!(meta_store.check_partition_coverage(index.get_id()).await.unwrap(), vec![
+                CoverageIssue::Gap { from: None, to: Some(row(2)) },
+                CoverageIssue::Overlap { first_partition_id: partitions[1].get_id(), second_partition_id: partitions[2].get_id() },
+                CoverageIssue::Gap { from: Some(row(30)), to: Some(row(35)) },
+                CoverageIssue::Gap { from: Some(row(40)), to: None },
+            ]);
+        }
+        RocksMetaStore::cleanup_test_metastore("check_partition_coverage_test");
+    }
+
+    #[actix_rt::test]
+    async fn find_partition_for_value_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("find_partition_for_value_test");
+        {
+            meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            let columns = vec![Column::new("col1".to_string(), ColumnType::Int, 0)];
+            let row = |v: i64| Row::new(vec![TableValue::Int(v)]);
+            let table = meta_store.create_table(
+                "foo".to_string(),
+                "boo".to_string(),
+                columns,
+                None,
+                None,
+                vec![],
+                vec![
+                    (Some(row(30)), None),
+                    (None, Some(row(10))),
+                    (Some(row(20)), Some(row(29))),
+                ]
+            ).await.unwrap();
+            let index = meta_store.get_default_index(table.get_id()).await.unwrap();
+            let partitions = meta_store.get_active_partitions_by_index_id(index.get_id()).await.unwrap();
+
+            let find = |v: i64| {
+                let meta_store = meta_store.clone();
+                let index_id = index.get_id();
+                async move {
+                    meta_store.find_partition_for_value(index_id, row(v)).await.unwrap().map(|p| p.get_id())
+                }
+            };
+            assert_eq!(find(-5).await, Some(partitions[1].get_id()));
+            assert_eq!(find(10).await, Some(partitions[1].get_id()));
+            assert_eq!(find(11).await, None);
+            assert_eq!(find(19).await, None);
+            assert_eq!(find(20).await, Some(partitions[2].get_id()));
+            assert_eq!(find(25).await, Some(partitions[2].get_id()));
+            assert_eq!(find(29).await, Some(partitions[2].get_id()));
+            assert_eq!(find(30).await, Some(partitions[0].get_id()));
+            assert_eq!(find(1000).await, Some(partitions[0].get_id()));
+        }
+        RocksMetaStore::cleanup_test_metastore("find_partition_for_value_test");
+    }
+
+    #[actix_rt::test]
+    async fn orphaned_jobs_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("orphaned_jobs_test");
+        {
+            let wal_job = meta_store.add_job(Job::new(RowKey::Table(TableId::WALs, 1), JobType::WalPartitioning, "node".to_string())).await.unwrap().unwrap();
+            let compaction_job = meta_store.add_job(Job::new(RowKey::Table(TableId::Partitions, 1), JobType::PartitionCompaction, "node".to_string())).await.unwrap().unwrap();
+            meta_store.start_processing_job("node".to_string()).await.unwrap().unwrap();
+            meta_store.start_processing_job("node".to_string()).await.unwrap().unwrap();
+            assert!(meta_store.find_orphaned_jobs().await.unwrap().is_empty());
+
+            let wal_job = meta_store.get_job(wal_job.get_id()).await.unwrap();
+            let compaction_job = meta_store.get_job(compaction_job.get_id()).await.unwrap();
+            let wal_timeout = JobType::WalPartitioning.heart_beat_timeout();
+            let compaction_timeout = JobType::PartitionCompaction.heart_beat_timeout();
+            let in_five_minutes = Utc::now() + chrono::Duration::minutes(5);
+            assert!(wal_job.get_row().is_orphaned(in_five_minutes, wal_timeout).unwrap());
+            assert!(!compaction_job.get_row().is_orphaned(in_five_minutes, compaction_timeout).unwrap());
+
+            let in_fifteen_minutes = Utc::now() + chrono::Duration::minutes(15);
+            assert!(compaction_job.get_row().is_orphaned(in_fifteen_minutes, compaction_timeout).unwrap());
+        }
+        RocksMetaStore::cleanup_test_metastore("orphaned_jobs_test");
+    }
+
+    #[actix_rt::test]
+    async fn find_orphaned_jobs_clock_test() {
+        let clock = Arc::new(TestClock::new(SystemTime::now()));
+        let config = MetaStoreConfig { clock: clock.clone(), ..MetaStoreConfig::default() };
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore_with_config("find_orphaned_jobs_clock_test", config);
+        {
+            let wal_job = meta_store.add_job(Job::new(RowKey::Table(TableId::WALs, 1), JobType::WalPartitioning, "node".to_string())).await.unwrap().unwrap();
+            let compaction_job = meta_store.add_job(Job::new(RowKey::Table(TableId::Partitions, 1), JobType::PartitionCompaction, "node".to_string())).await.unwrap().unwrap();
+            meta_store.start_processing_job("node".to_string()).await.unwrap().unwrap();
+            meta_store.start_processing_job("node".to_string()).await.unwrap().unwrap();
+            assert!(meta_store.find_orphaned_jobs().await.unwrap().is_empty());
+
+            clock.advance(Duration::from_secs(5 * 60));
+            let orphaned = meta_store.find_orphaned_jobs().await.unwrap();
+            assert_eq!(orphaned.iter().map(|j| j.get_id()).collect::<Vec<_>>(), vec![wal_job.get_id()]);
+
+            clock.advance(Duration::from_secs(10 * 60));
+            let orphaned = meta_store.find_orphaned_jobs().await.unwrap();
+            assert_eq!(orphaned.iter().map(|j| j.get_id()).collect::<Vec<_>>(), vec![wal_job.get_id(), compaction_job.get_id()]);
+        }
+        RocksMetaStore::cleanup_test_metastore("find_orphaned_jobs_clock_test");
+    }
+
+    #[actix_rt::test]
+    async fn heart_beat_timeout_override_test() {
+        let clock = Arc::new(TestClock::new(SystemTime::now()));
+        let mut heart_beat_timeouts = HashMap::new();
+        heart_beat_timeouts.insert(JobType::PartitionCompaction, Duration::from_secs(30 * 60));
+        let config = MetaStoreConfig { clock: clock.clone(), heart_beat_timeouts, ..MetaStoreConfig::default() };
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore_with_config("heart_beat_timeout_override_test", config);
+        {
+            let wal_job = meta_store.add_job(Job::new(RowKey::Table(TableId::WALs, 1), JobType::WalPartitioning, "node".to_string())).await.unwrap().unwrap();
+            let compaction_job = meta_store.add_job(Job::new(RowKey::Table(TableId::Partitions, 1), JobType::PartitionCompaction, "node".to_string())).await.unwrap().unwrap();
+            meta_store.start_processing_job("node".to_string()).await.unwrap().unwrap();
+            meta_store.start_processing_job("node".to_string()).await.unwrap().unwrap();
+
+            // Job types without an override keep the default timeout
+            clock.advance(Duration::from_secs(15 * 60));
+            let orphaned = meta_store.find_orphaned_jobs().await.unwrap();
+            assert_eq!(orphaned.iter().map(|j| j.get_id()).collect::<Vec<_>>(), vec![wal_job.get_id()]);
+
+            clock.advance(Duration::from_secs(20 * 60));
+            let orphaned = meta_store.find_orphaned_jobs().await.unwrap();
+            assert_eq!(orphaned.iter().map(|j| j.get_id()).collect::<Vec<_>>(), vec![wal_job.get_id(), compaction_job.get_id()]);
+        }
+        RocksMetaStore::cleanup_test_metastore("heart_beat_timeout_override_test");
+    }
+
+    #[actix_rt::test]
+    async fn update_heart_beats_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("update_heart_beats_test");
+        {
+            let wal_job = meta_store.add_job(Job::new(RowKey::Table(TableId::WALs, 1), JobType::WalPartitioning, "node".to_string())).await.unwrap().unwrap();
+            let compaction_job = meta_store.add_job(Job::new(RowKey::Table(TableId::Partitions, 1), JobType::PartitionCompaction, "node".to_string())).await.unwrap().unwrap();
+            meta_store.start_processing_job("node".to_string()).await.unwrap().unwrap();
+            meta_store.start_processing_job("node".to_string()).await.unwrap().unwrap();
+            let wal_job = meta_store.get_job(wal_job.get_id()).await.unwrap();
+            let compaction_job = meta_store.get_job(compaction_job.get_id()).await.unwrap();
+
+            tokio::time::delay_for(Duration::from_millis(10)).await;
+            meta_store.update_heart_beats(vec![wal_job.get_id(), compaction_job.get_id()]).await.unwrap();
+
+            for job in vec![wal_job, compaction_job] {
+                let updated = meta_store.get_job(job.get_id()).await.unwrap();
+                assert!(updated.get_row().last_heart_beat() > job.get_row().last_heart_beat());
+            }
+            assert!(meta_store.start_processing_job("node".to_string()).await.unwrap().is_none());
+            assert!(meta_store.check_integrity().await.unwrap().is_empty());
+
+            assert!(meta_store.update_heart_beats(vec![12345]).await.is_err());
+        }
+        RocksMetaStore::cleanup_test_metastore("update_heart_beats_test");
+    }
+
+    #[actix_rt::test]
+    async fn tables_using_column_type_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("tables_using_column_type_test");
+        {
+            meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            let decimal_table = meta_store.create_table(
+                "foo".to_string(),
+                "with_decimal".to_string(),
+                vec![Column::new("col1".to_string(), ColumnType::Int, 0), Column::new("col2".to_string(), ColumnType::Decimal, 1)],
+                None,
+                None,
+                vec![],
+                vec![]
+            ).await.unwrap();
+            meta_store.create_table(
+                "foo".to_string(),
+                "without_decimal".to_string(),
+                vec![Column::new("col1".to_string(), ColumnType::Int, 0), Column::new("col2".to_string(), ColumnType::String, 1)],
+                None,
+                None,
+                vec![],
+                vec![]
+            ).await.unwrap();
+
+            assert_eq!(meta_store.get_tables_using_column_type(ColumnType::Decimal).await.unwrap(), vec![decimal_table]);
+            assert_eq!(meta_store.get_tables_using_column_type(ColumnType::Int).await.unwrap().len(), 2);
+            assert!(meta_store.get_tables_using_column_type(ColumnType::Boolean).await.unwrap().is_empty());
+        }
+        RocksMetaStore::cleanup_test_metastore("tables_using_column_type_test");
+    }
+
+    #[actix_rt::test]
+    async fn table_column_types_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("table_column_types_test");
+        {
+            meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            let table = meta_store.create_table(
+                "foo".to_string(),
+                "boo".to_string(),
+                vec![
+                    Column::new("col1".to_string(), ColumnType::Int, 0),
+                    Column::new("col2".to_string(), ColumnType::Timestamp, 1),
+                    Column::new("col3".to_string(), ColumnType::Float, 2)
+                ],
+                None,
+                None,
+                vec![],
+                vec![]
+            ).await.unwrap();
+
+            assert_eq!(
+                meta_store.get_table_column_types(table.get_id()).await.unwrap(),
+                vec![
+                    ("col1".to_string(), ColumnType::Int),
+                    ("col2".to_string(), ColumnType::Timestamp),
+                    ("col3".to_string(), ColumnType::Float)
+                ]
+            );
+            assert!(meta_store.get_table_column_types(table.get_id() + 1).await.is_err());
+        }
+        RocksMetaStore::cleanup_test_metastore("table_column_types_test");
+    }
+
+    #[actix_rt::test]
+    async fn resolve_tables_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("resolve_tables_test");
+        {
+            meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            meta_store.create_schema("bar".to_string(), false).await.unwrap();
+            let mut tables = Vec::new();
+            for (schema, table) in vec![("foo", "t1"), ("foo", "t2"), ("bar", "t1")] {
+                tables.push(meta_store.create_table(
+                    schema.to_string(),
+                    table.to_string(),
+                    vec![Column::new("col1".to_string(), ColumnType::Int, 0)],
+                    None,
+                    None,
+                    vec![],
+                    vec![]
+                ).await.unwrap());
+            }
+
+            let refs = vec![
+                ("foo".to_string(), "t1".to_string()),
+                ("foo".to_string(), "t2".to_string()),
+                ("bar".to_string(), "t1".to_string()),
+                ("foo".to_string(), "missing".to_string()),
+                ("missing".to_string(), "t1".to_string()),
+            ];
+            let resolved = meta_store.resolve_tables(refs).await.unwrap();
+            assert_eq!(resolved.len(), 3);
+            assert_eq!(resolved.get(&("foo".to_string(), "t1".to_string())), Some(&tables[0]));
+            assert_eq!(resolved.get(&("foo".to_string(), "t2".to_string())), Some(&tables[1]));
+            assert_eq!(resolved.get(&("bar".to_string(), "t1".to_string())), Some(&tables[2]));
+            assert!(resolved.get(&("foo".to_string(), "missing".to_string())).is_none());
+        }
+        RocksMetaStore::cleanup_test_metastore("resolve_tables_test");
+    }
+
+    #[actix_rt::test]
+    async fn verify_on_start_test() {
+        let store_path = env::current_dir().unwrap().join("test-verify_on_start_test-local");
+        let (remote_fs, meta_store) = RocksMetaStore::prepare_test_metastore("verify_on_start_test");
+        let table_id = {
+            meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            let table = meta_store.create_table(
+                "foo".to_string(),
+                "t1".to_string(),
+                vec![Column::new("col1".to_string(), ColumnType::Int, 0)],
+                None,
+                None,
+                vec![],
+                vec![]
+            ).await.unwrap();
+            assert!(meta_store.check_integrity().await.unwrap().is_empty());
+            table.get_id()
+        };
+        drop(meta_store);
+
+        let meta_store_path = store_path.join("metastore");
+        let meta_store = RocksMetaStore::load_from_remote(meta_store_path.as_path(), remote_fs.clone(), MetaStoreConfig::default(), true).await.unwrap();
+        {
+            let db = meta_store.db.read().await.clone();
+            db.delete(RowKey::Table(TableId::Tables, table_id).to_bytes()).unwrap();
+        }
+        drop(meta_store);
+
+        let meta_store = RocksMetaStore::load_from_remote(meta_store_path.as_path(), remote_fs.clone(), MetaStoreConfig::default(), false).await.unwrap();
+        let discrepancies = meta_store.check_integrity().await.unwrap();
+        assert!(discrepancies.iter().any(|d| d.contains(&format!("references missing table {}", table_id))));
+        drop(meta_store);
+
+        let res = RocksMetaStore::load_from_remote(meta_store_path.as_path(), remote_fs.clone(), MetaStoreConfig::default(), true).await;
+        assert!(res.is_err());
+        assert!(res.err().unwrap().message.contains("integrity check"));
+
+        RocksMetaStore::cleanup_test_metastore("verify_on_start_test");
+    }
+
+    #[actix_rt::test]
+    async fn get_all_wals_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("get_all_wals_test");
+        {
+            meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            let mut table_ids = Vec::new();
+            for table_name in vec!["t1", "t2"] {
+                table_ids.push(meta_store.create_table(
+                    "foo".to_string(),
+                    table_name.to_string(),
+                    vec![Column::new("col1".to_string(), ColumnType::Int, 0)],
+                    None,
+                    None,
+                    vec![],
+                    vec![]
+                ).await.unwrap().get_id());
+            }
+
+            let wal_1 = meta_store.create_wal(table_ids[0], 10).await.unwrap();
+            let wal_2 = meta_store.create_wal(table_ids[0], 20).await.unwrap();
+            let wal_3 = meta_store.create_wal(table_ids[1], 30).await.unwrap();
+            meta_store.wal_uploaded(wal_2.get_id()).await.unwrap();
+
+            let all_ids = meta_store.get_all_wals().await.unwrap().into_iter().map(|w| w.get_id()).collect::<Vec<_>>();
+            assert_eq!(all_ids, vec![wal_1.get_id(), wal_2.get_id(), wal_3.get_id()]);
+
+            let pending_ids = meta_store.get_all_wals_pending_upload().await.unwrap().into_iter().map(|w| w.get_id()).collect::<Vec<_>>();
+            assert_eq!(pending_ids, vec![wal_1.get_id(), wal_3.get_id()]);
+        }
+        RocksMetaStore::cleanup_test_metastore("get_all_wals_test");
+    }
+
+    #[actix_rt::test]
+    async fn truncate_table_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("truncate_table_test");
+        {
+            meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            let table = meta_store.create_table(
+                "foo".to_string(),
+                "t1".to_string(),
+                vec![Column::new("col1".to_string(), ColumnType::Int, 0)],
+                None,
+                None,
+                vec![],
+                vec![(None, Some(Row::new(vec![TableValue::Int(10)]))), (Some(Row::new(vec![TableValue::Int(10)])), None)]
+            ).await.unwrap();
+            let index = meta_store.get_default_index(table.get_id()).await.unwrap();
+            let partitions = meta_store.get_active_partitions_by_index_id(index.get_id()).await.unwrap();
+            assert_eq!(partitions.len(), 2);
+            let chunk = meta_store.create_chunk(partitions[0].get_id(), 10).await.unwrap();
+            meta_store.create_wal(table.get_id(), 5).await.unwrap();
+
+            meta_store.truncate_table(table.get_id()).await.unwrap();
+
+            assert_eq!(meta_store.get_table_by_id(table.get_id()).await.unwrap(), table);
+            assert_eq!(meta_store.get_table_indexes(table.get_id()).await.unwrap(), vec![index.clone()]);
+            let new_partitions = meta_store.get_active_partitions_by_index_id(index.get_id()).await.unwrap();
+            assert_eq!(new_partitions.len(), 1);
+            assert!(partitions.iter().all(|p| p.get_id() != new_partitions[0].get_id()));
+            assert_eq!(new_partitions[0].get_row().get_min_val(), &None);
+            assert_eq!(new_partitions[0].get_row().get_max_val(), &None);
+            assert!(meta_store.get_chunks_by_partition(new_partitions[0].get_id()).await.unwrap().is_empty());
+            assert!(!meta_store.chunks_table().row_exists(chunk.get_id()).await.unwrap());
+            assert!(meta_store.get_wals_for_table(table.get_id()).await.unwrap().is_empty());
+        }
+        RocksMetaStore::cleanup_test_metastore("truncate_table_test");
+    }
+
+    #[actix_rt::test]
+    async fn partition_timestamps_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("partition_timestamps_test");
+        {
+            let before_create = SystemTime::now();
+            meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            let table = meta_store.create_table(
+                "foo".to_string(),
+                "t1".to_string(),
+                vec![Column::new("col1".to_string(), ColumnType::Int, 0)],
+                None,
+                None,
+                vec![],
+                vec![]
+            ).await.unwrap();
+            let index = meta_store.get_default_index(table.get_id()).await.unwrap();
+            let partition_id = meta_store.get_active_partitions_by_index_id(index.get_id()).await.unwrap()[0].get_id();
+
+            let created = meta_store.get_partition(partition_id).await.unwrap();
+            assert!(created.get_row().created_at() >= before_create);
+            assert!(created.get_row().created_at() <= SystemTime::now());
+            assert_eq!(created.get_row().last_modified(), created.get_row().created_at());
+
+            tokio::time::delay_for(Duration::from_millis(10)).await;
+            meta_store.swap_active_partitions(vec![partition_id], vec![], vec![], vec![], None, None).await.unwrap();
+
+            let updated = meta_store.get_partition(partition_id).await.unwrap();
+            assert!(!updated.get_row().is_active());
+            assert_eq!(updated.get_row().created_at(), created.get_row().created_at());
+            assert!(updated.get_row().last_modified() > created.get_row().last_modified());
+        }
+        RocksMetaStore::cleanup_test_metastore("partition_timestamps_test");
+    }
+
+    #[actix_rt::test]
+    async fn cache_full_catalog_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("cache_full_catalog_test");
+        {
+            meta_store.set_cache_full_catalog(true).await;
+            meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            let t1 = meta_store.create_table(
+                "foo".to_string(),
+                "t1".to_string(),
+                vec![Column::new("col1".to_string(), ColumnType::Int, 0)],
+                None,
+                None,
+                vec![],
+                vec![]
+            ).await.unwrap();
+            assert_eq!(meta_store.get_table("foo".to_string(), "t1".to_string()).await.unwrap(), t1);
+
+            // Remove row behind the cache's back: reads should still be served from memory
+            {
+                let db = meta_store.db.read().await.clone();
+                db.delete(RowKey::Table(TableId::Tables, t1.get_id()).to_bytes()).unwrap();
+            }
+            assert_eq!(meta_store.get_table("foo".to_string(), "t1".to_string()).await.unwrap(), t1);
+            assert_eq!(meta_store.get_tables().await.unwrap(), vec![t1.clone()]);
+            assert_eq!(meta_store.get_schema("foo".to_string()).await.unwrap().get_row().get_name(), "foo");
+
+            // DDL invalidates the cache so next read reloads from RocksDB
+            let t2 = meta_store.create_table(
+                "foo".to_string(),
+                "t2".to_string(),
+                vec![Column::new("col1".to_string(), ColumnType::Int, 0)],
+                None,
+                None,
+                vec![],
+                vec![]
+            ).await.unwrap();
+            assert_eq!(meta_store.get_tables().await.unwrap(), vec![t2.clone()]);
+            assert_eq!(meta_store.get_table("foo".to_string(), "t2".to_string()).await.unwrap(), t2);
+            assert_eq!(meta_store.get_table_indexes(t2.get_id()).await.unwrap().len(), 1);
+
+            meta_store.rename_schema("foo".to_string(), "bar".to_string()).await.unwrap();
+            assert!(meta_store.get_schema("foo".to_string()).await.is_err());
+            assert_eq!(meta_store.get_table("bar".to_string(), "t2".to_string()).await.unwrap(), t2);
+        }
+        RocksMetaStore::cleanup_test_metastore("cache_full_catalog_test");
+    }
+
+    #[actix_rt::test]
+    async fn oldest_chunks_by_partition_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("oldest_chunks_by_partition_test");
+        {
+            meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            let table = meta_store.create_table(
+                "foo".to_string(),
+                "t1".to_string(),
+                vec![Column::new("col1".to_string(), ColumnType::Int, 0)],
+                None,
+                None,
+                vec![],
+                vec![]
+            ).await.unwrap();
+            let index = meta_store.get_default_index(table.get_id()).await.unwrap();
+            let partition_id = meta_store.get_active_partitions_by_index_id(index.get_id()).await.unwrap()[0].get_id();
+
+            let mut chunk_ids = Vec::new();
+            for i in 0..6 {
+                let chunk = meta_store.create_chunk(partition_id, 10).await.unwrap();
+                // Second chunk isn't uploaded and third one is deactivated so both are skipped
+                if i != 1 {
+                    meta_store.chunk_uploaded(chunk.get_id()).await.unwrap();
+                }
+                if i == 2 {
+                    meta_store.deactivate_chunk(chunk.get_id()).await.unwrap();
+                }
+                chunk_ids.push(chunk.get_id());
+            }
+
+            let oldest = meta_store.get_oldest_chunks_by_partition(partition_id, 2).await.unwrap();
+            assert_eq!(oldest.iter().map(|c| c.get_id()).collect::<Vec<_>>(), vec![chunk_ids[0], chunk_ids[3]]);
+
+            let all = meta_store.get_oldest_chunks_by_partition(partition_id, 100).await.unwrap();
+            assert_eq!(all.iter().map(|c| c.get_id()).collect::<Vec<_>>(), vec![chunk_ids[0], chunk_ids[3], chunk_ids[4], chunk_ids[5]]);
+        }
+        RocksMetaStore::cleanup_test_metastore("oldest_chunks_by_partition_test");
+    }
+
+    #[actix_rt::test]
+    async fn corrupt_row_key_test() {
+        assert_eq!(RowKey::from_bytes(&[]).unwrap_err().cause(), CubeErrorCause::Corruption);
+        assert_eq!(RowKey::from_bytes(&[9, 0, 0, 1, 0]).unwrap_err().cause(), CubeErrorCause::Corruption);
+        assert_eq!(RowKey::from_bytes(&[2, 0, 0, 0x7f, 0]).unwrap_err().cause(), CubeErrorCause::Corruption);
+        assert_eq!(RowKey::from_bytes(&[3, 0, 0, 1]).unwrap_err().cause(), CubeErrorCause::Corruption);
+
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("corrupt_row_key_test");
+        {
+            let schema = meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            let db = meta_store.db.read().await.clone();
+            // Truncated key sharing the table prefix sorts before every valid row
+            db.put(&RowKey::Table(TableId::Schemas, 0).to_bytes()[0..15], vec![]).unwrap();
+
+            let table = SchemaRocksTable::new(db.clone());
+            let mut scan = table.table_scan(&db).unwrap();
+            assert_eq!(scan.next().unwrap().unwrap_err().cause(), CubeErrorCause::Corruption);
+            assert_eq!(scan.next().unwrap().unwrap().get_id(), schema.get_id());
+            assert!(scan.next().is_none());
+
+            assert_eq!(meta_store.get_schemas().await.unwrap_err().cause(), CubeErrorCause::Corruption);
+        }
+        RocksMetaStore::cleanup_test_metastore("corrupt_row_key_test");
+    }
+
+    #[actix_rt::test]
+    async fn corrupt_row_value_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("corrupt_row_value_test");
+        {
+            let foo = meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            let bar = meta_store.create_schema("bar".to_string(), false).await.unwrap();
+            let db = meta_store.db.read().await.clone();
+            // Zero-length value as left behind by a disk-full event and a value of the wrong shape
+            db.put(&RowKey::Table(TableId::Schemas, bar.get_id() + 1).to_bytes(), vec![]).unwrap();
+            let mut ser = flexbuffers::FlexbufferSerializer::new();
+            42u64.serialize(&mut ser).unwrap();
+            db.put(&RowKey::Table(TableId::Schemas, bar.get_id() + 2).to_bytes(), ser.take_buffer()).unwrap();
+
+            let table = SchemaRocksTable::new(db.clone());
+            assert_eq!(table.get_row(bar.get_id() + 1).unwrap_err().cause(), CubeErrorCause::Corruption);
+            assert_eq!(table.get_row(bar.get_id() + 2).unwrap_err().cause(), CubeErrorCause::Corruption);
+
+            assert_eq!(table.all_rows().unwrap_err().cause(), CubeErrorCause::Corruption);
+            assert_eq!(meta_store.get_schemas().await.unwrap_err().cause(), CubeErrorCause::Corruption);
+            assert_eq!(
+                table.all_rows_lenient().unwrap().into_iter().map(|r| r.get_id()).collect::<Vec<_>>(),
+                vec![foo.get_id(), bar.get_id()]
+            );
+        }
+        RocksMetaStore::cleanup_test_metastore("corrupt_row_value_test");
+    }
+
+    #[actix_rt::test]
+    async fn vacuum_orphan_index_entries_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("vacuum_orphan_index_entries_test");
+        {
+            meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            meta_store.create_schema("bar".to_string(), false).await.unwrap();
+            let columns = vec![Column::new("col1".to_string(), ColumnType::Int, 0)];
+            let table = meta_store.create_table("foo".to_string(), "boo".to_string(), columns, None, None, vec![], vec![]).await.unwrap();
+            assert_eq!(meta_store.vacuum_orphan_index_entries().await.unwrap(), 0);
+
+            let db = meta_store.db.read().await.clone();
+            let schemas = SchemaRocksTable::new(db.clone());
+            for key_val in schemas.insert_index_row(&Schema { name: "ghost".to_string() }, 999).unwrap() {
+                db.put(key_val.key, key_val.val).unwrap();
+            }
+            let tables = TableRocksTable::new(db.clone());
+            for key_val in tables.insert_index_row(&table.get_row().clone(), 998).unwrap() {
+                db.put(key_val.key, key_val.val).unwrap();
+            }
+
+            assert_eq!(meta_store.vacuum_orphan_index_entries().await.unwrap(), 3);
+            assert_eq!(meta_store.vacuum_orphan_index_entries().await.unwrap(), 0);
+
+            assert!(meta_store.get_schema("ghost".to_string()).await.is_err());
+            meta_store.get_schema("foo".to_string()).await.unwrap();
+            meta_store.get_schema("bar".to_string()).await.unwrap();
+            assert_eq!(meta_store.get_table("foo".to_string(), "boo".to_string()).await.unwrap(), table);
+            assert!(meta_store.check_integrity().await.unwrap().is_empty());
+        }
+        RocksMetaStore::cleanup_test_metastore("vacuum_orphan_index_entries_test");
+    }
+
+    #[test]
+    fn table_id_discriminants_test() {
+        let expected = vec![
+            (TableId::Schemas, 0x0100),
+            (TableId::Tables, 0x0200),
+            (TableId::Indexes, 0x0300),
+            (TableId::Partitions, 0x0400),
+            (TableId::Chunks, 0x0500),
+            (TableId::WALs, 0x0600),
+            (TableId::Jobs, 0x0700),
+            (TableId::IndexStats, 0x0800),
+        ];
+        for (table_id, value) in expected.into_iter() {
+            assert_eq!(table_id as u32, value);
+            assert_eq!(TableId::from(value), table_id);
+
+            let key = RowKey::Table(table_id, 42);
+            assert_eq!(&key.to_bytes()[0..5], &[1, 0, 0, (value >> 8) as u8, value as u8]);
+            assert_eq!(RowKey::from_bytes(&key.to_bytes()).unwrap(), key);
+            let seq_key = RowKey::Sequence(table_id);
+            assert_eq!(seq_key.to_bytes(), vec![2, 0, 0, (value >> 8) as u8, value as u8]);
+            assert_eq!(RowKey::from_bytes(&seq_key.to_bytes()).unwrap(), seq_key);
+        }
+    }
+
+    #[actix_rt::test]
+    async fn apply_log_from_file_test() {
+        let (remote_fs, meta_store) = RocksMetaStore::prepare_test_metastore("apply_log_from_file_test");
+        let (_, restored_store) = RocksMetaStore::prepare_test_metastore("apply_log_from_file_restored_test");
+        {
+            for i in 0..2000 {
+                meta_store.create_schema(format!("foo{}", i), false).await.unwrap();
+            }
+            for i in 0..500 {
+                meta_store.delete_schema(format!("foo{}", i * 4)).await.unwrap();
+            }
+
+            let mut container = WriteBatchContainer::new();
+            for (_, write_batch) in meta_store.db.read().await.get_updates_since(0).unwrap() {
+                write_batch.iterate(&mut container);
+            }
+            let log_file = remote_fs.local_file("test.flex").await.unwrap();
+            fs::create_dir_all(Path::new(&log_file).parent().unwrap()).unwrap();
+            container.write_to_file(&log_file).await.unwrap();
+
+            let applied = {
+                let db = restored_store.db.write().await;
+                WriteBatchContainer::apply_from_file(&log_file, &db, 100).await.unwrap()
+            };
+            assert_eq!(applied, container.entries.len());
+
+            let restored_schemas = restored_store.get_schemas().await.unwrap();
+            assert_eq!(restored_schemas.len(), 1500);
+            assert_eq!(restored_schemas, meta_store.get_schemas().await.unwrap());
+            assert!(restored_store.get_schema("foo0".to_string()).await.is_err());
+            assert_eq!(restored_store.get_schema("foo1".to_string()).await.unwrap(), meta_store.get_schema("foo1".to_string()).await.unwrap());
+        }
+        RocksMetaStore::cleanup_test_metastore("apply_log_from_file_test");
+        RocksMetaStore::cleanup_test_metastore("apply_log_from_file_restored_test");
+    }
+
+    #[actix_rt::test]
+    async fn schemas_with_counts_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("schemas_with_counts_test");
+        {
+            meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            meta_store.create_schema("bar".to_string(), false).await.unwrap();
+            meta_store.create_schema("empty".to_string(), false).await.unwrap();
+            let columns = vec![Column::new("col1".to_string(), ColumnType::Int, 0), Column::new("col2".to_string(), ColumnType::Int, 1)];
+            meta_store.create_table("foo".to_string(), "t1".to_string(), columns.clone(), None, None, vec![], vec![]).await.unwrap();
+            meta_store.create_table(
+                "foo".to_string(),
+                "t2".to_string(),
+                columns.clone(),
+                None,
+                None,
+                vec![IndexDef { name: "by_col2".to_string(), columns: vec!["col2".to_string()] }],
+                vec![]
+            ).await.unwrap();
+            meta_store.create_table("bar".to_string(), "t1".to_string(), columns.clone(), None, None, vec![], vec![]).await.unwrap();
+
+            let counts = meta_store.get_schemas_with_counts().await.unwrap()
+                .into_iter()
+                .map(|(s, tables, indexes)| (s.get_row().get_name().to_string(), tables, indexes))
+                .collect::<Vec<_>>();
+            assert_eq!(counts, vec![
+                ("foo".to_string(), 2, 3),
+                ("bar".to_string(), 1, 1),
+                ("empty".to_string(), 0, 0),
+            ]);
+        }
+        RocksMetaStore::cleanup_test_metastore("schemas_with_counts_test");
+    }
+
+    #[actix_rt::test]
+    async fn schema_usage_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("schema_usage_test");
+        {
+            let foo = meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            let bar = meta_store.create_schema("bar".to_string(), false).await.unwrap();
+            let columns = vec![Column::new("col1".to_string(), ColumnType::Int, 0)];
+            let mut partitions = Vec::new();
+            for (schema, table) in vec![("foo", "t1"), ("foo", "t2"), ("bar", "t3")] {
+                let table = meta_store.create_table(schema.to_string(), table.to_string(), columns.clone(), None, None, vec![], vec![]).await.unwrap();
+                let index = meta_store.get_default_index(table.get_id()).await.unwrap();
+                partitions.push(meta_store.get_active_partitions_by_index_id(index.get_id()).await.unwrap()[0].clone());
+            }
+
+            let chunk = meta_store.create_chunk(partitions[0].get_id(), 10).await.unwrap();
+            meta_store.chunk_uploaded(chunk.get_id()).await.unwrap();
+            // Not uploaded yet so not counted
+            meta_store.create_chunk(partitions[0].get_id(), 5).await.unwrap();
+
+            let child = meta_store.create_partition(partitions[1].get_row().child(partitions[1].get_id())).await.unwrap();
+            meta_store.swap_active_partitions(vec![partitions[1].get_id()], vec![child.get_id()], vec![], vec![(100, (None, None))], None, None).await.unwrap();
+            let chunk = meta_store.create_chunk(child.get_id(), 7).await.unwrap();
+            meta_store.chunk_uploaded(chunk.get_id()).await.unwrap();
+
+            let chunk = meta_store.create_chunk(partitions[2].get_id(), 3).await.unwrap();
+            meta_store.chunk_uploaded(chunk.get_id()).await.unwrap();
+
+            assert_eq!(meta_store.get_schema_usage(foo.get_id()).await.unwrap(), SchemaUsage { table_count: 2, total_rows: 117 });
+            assert_eq!(meta_store.get_schema_usage(bar.get_id()).await.unwrap(), SchemaUsage { table_count: 1, total_rows: 3 });
+            assert!(meta_store.get_schema_usage(bar.get_id() + 1).await.is_err());
+        }
+        RocksMetaStore::cleanup_test_metastore("schema_usage_test");
+    }
+
+    #[actix_rt::test]
+    async fn partition_last_activation_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("partition_last_activation_test");
+        {
+            meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            let columns = vec![Column::new("col1".to_string(), ColumnType::Int, 0)];
+            let table = meta_store.create_table("foo".to_string(), "boo".to_string(), columns, None, None, vec![], vec![]).await.unwrap();
+            let index = meta_store.get_default_index(table.get_id()).await.unwrap();
+            let partition = meta_store.get_active_partitions_by_index_id(index.get_id()).await.unwrap()[0].clone();
+            assert_eq!(partition.get_row().last_activation(), &None);
+
+            let child = meta_store.create_partition(partition.get_row().child(partition.get_id())).await.unwrap();
+            assert_eq!(child.get_row().last_activation(), &None);
+
+            let before_swap = SystemTime::now();
+            meta_store.swap_active_partitions(
+                vec![partition.get_id()],
+                vec![child.get_id()],
+                vec![],
+                vec![(0, (None, None))],
+                None,
+                Some(42)
+            ).await.unwrap();
+
+            let deactivated = meta_store.get_partition(partition.get_id()).await.unwrap();
+            let (deactivated_at, deactivated_by) = deactivated.get_row().last_activation().unwrap();
+            assert!(!deactivated.get_row().is_active());
+            assert!(deactivated_at >= before_swap);
+            assert_eq!(deactivated_by, Some(42));
+
+            let activated = meta_store.get_partition(child.get_id()).await.unwrap();
+            let (activated_at, activated_by) = activated.get_row().last_activation().unwrap();
+            assert!(activated.get_row().is_active());
+            assert!(activated_at >= before_swap);
+            assert_eq!(activated_by, Some(42));
+        }
+        RocksMetaStore::cleanup_test_metastore("partition_last_activation_test");
+    }
+
+    #[actix_rt::test]
+    async fn unique_index_lookup_short_circuit_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("unique_index_lookup_short_circuit_test");
+        {
+            let schema = meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            let db = meta_store.db.read().await.clone();
+            let schemas = SchemaRocksTable::new(db.clone());
+            // Dangling duplicate entry with the same key: unique lookup must stop at the first match
+            for index_row in schemas.insert_index_row(schema.get_row(), 999).unwrap() {
+                db.put(index_row.key, index_row.val).unwrap();
+            }
+
+            let index = SchemaRocksIndex::Name;
+            let key = "foo".to_string();
+            let hash = index.typed_key_hash(&key).to_be_bytes().to_vec();
+            let val = index.key_to_bytes(&key);
+            let id = RocksSecondaryIndex::get_id(&index);
+            assert_eq!(schemas.get_row_from_index(id, &val, &hash, true).unwrap(), vec![schema.get_id()]);
+            assert_eq!(schemas.get_row_from_index(id, &val, &hash, false).unwrap(), vec![schema.get_id(), 999]);
+
+            // Value bytes are still verified on the fast path
+            let other_val = index.key_to_bytes(&"bar".to_string());
+            assert!(schemas.get_row_from_index(id, &other_val, &hash, true).unwrap().is_empty());
+
+            for _ in 0..1000 {
+                assert_eq!(schemas.get_row_from_index(id, &val, &hash, true).unwrap(), vec![schema.get_id()]);
+            }
+        }
+        RocksMetaStore::cleanup_test_metastore("unique_index_lookup_short_circuit_test");
+    }
+
+    #[actix_rt::test]
+    async fn unique_index_duplicate_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("unique_index_duplicate_test");
+        {
+            let foo = meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            let bar = meta_store.create_schema("bar".to_string(), false).await.unwrap();
+            let db = meta_store.db.read().await.clone();
+            let schemas = SchemaRocksTable::new(db.clone());
+            // Index entry for bar under the name of foo: both rows exist so only the unique check can catch it
+            for index_row in schemas.insert_index_row(foo.get_row(), bar.get_id()).unwrap() {
+                db.put(index_row.key, index_row.val).unwrap();
+            }
+
+            let key = "foo".to_string();
+            assert_eq!(schemas.get_row_ids_by_index(&key, &SchemaRocksIndex::Name).unwrap(), vec![foo.get_id(), bar.get_id()]);
+            let err = schemas.get_rows_by_index(&key, &SchemaRocksIndex::Name).unwrap_err();
+            assert_eq!(err.cause(), CubeErrorCause::Corruption);
+            assert!(err.message.contains("Unique index expected but found multiple values"), "Unexpected error: {}", err);
+            let err = schemas.get_single_row_by_index(&key, &SchemaRocksIndex::Name).unwrap_err();
+            assert_eq!(err.cause(), CubeErrorCause::Corruption);
+        }
+        RocksMetaStore::cleanup_test_metastore("unique_index_duplicate_test");
+    }
+
+    #[actix_rt::test]
+    async fn ordered_index_range_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("ordered_index_range_test");
+        {
+            let row = |v: i64| Row::new(vec![TableValue::Int(v)]);
+            let mut ids = Vec::new();
+            for min_value in vec![Some(row(30)), None, Some(row(-5)), Some(row(20)), Some(row(40)), Some(row(10))] {
+                ids.push(meta_store.create_partition(Partition::new(42, min_value, None)).await.unwrap().get_id());
+            }
+            meta_store.create_partition(Partition::new(43, Some(row(15)), None)).await.unwrap();
+            let string_row = |v: &str| Row::new(vec![TableValue::String(v.to_string())]);
+            let mut string_ids = Vec::new();
+            for min_value in vec!["b", "a\0", "ab", "a"] {
+                string_ids.push(meta_store.create_partition(Partition::new(44, Some(string_row(min_value)), None)).await.unwrap().get_id());
+            }
+
+            let db = meta_store.db.read().await.clone();
+            let partitions = PartitionRocksTable::new(db);
+            let range_ids = |from: PartitionIndexKey, to: PartitionIndexKey| partitions
+                .get_rows_by_index_range(&from, &to, &PartitionRocksIndex::MinValue).unwrap()
+                .into_iter().map(|p| p.get_id()).collect::<Vec<_>>();
+
+            assert_eq!(
+                range_ids(PartitionIndexKey::ByMinValue(42, Some(row(10))), PartitionIndexKey::ByMinValue(42, Some(row(30)))),
+                vec![ids[5], ids[3], ids[0]]
+            );
+            assert_eq!(
+                range_ids(PartitionIndexKey::ByMinValue(42, None), PartitionIndexKey::ByMinValue(42, Some(row(10)))),
+                vec![ids[1], ids[2], ids[5]]
+            );
+            assert_eq!(
+                range_ids(PartitionIndexKey::ByMinValue(42, Some(row(41))), PartitionIndexKey::ByMinValue(43, None)),
+                Vec::<u64>::new()
+            );
+            assert_eq!(
+                range_ids(PartitionIndexKey::ByMinValue(44, None), PartitionIndexKey::ByMinValue(44, Some(string_row("b")))),
+                vec![string_ids[3], string_ids[1], string_ids[2], string_ids[0]]
+            );
+
+            let exact = partitions.get_rows_by_index(&PartitionIndexKey::ByMinValue(42, Some(row(20))), &PartitionRocksIndex::MinValue).unwrap();
+            assert_eq!(exact.into_iter().map(|p| p.get_id()).collect::<Vec<_>>(), vec![ids[3]]);
+            assert!(partitions.get_rows_by_index_range(
+                &PartitionIndexKey::ByIndexId(42), &PartitionIndexKey::ByIndexId(42), &PartitionRocksIndex::IndexId
+            ).is_err());
+
+            // Updating min_value moves the ordered index entry
+            let parent = meta_store.get_partition(ids[3]).await.unwrap();
+            let child = meta_store.create_partition(parent.get_row().child(parent.get_id())).await.unwrap();
+            let unbounded = partitions.get_rows_by_index(&PartitionIndexKey::ByMinValue(42, None), &PartitionRocksIndex::MinValue).unwrap();
+            assert_eq!(unbounded.into_iter().map(|p| p.get_id()).collect::<Vec<_>>(), vec![ids[1], child.get_id()]);
+            meta_store.swap_active_partitions(vec![ids[3]], vec![child.get_id()], vec![], vec![(0, (Some(row(25)), None))], None, None).await.unwrap();
+            let unbounded = partitions.get_rows_by_index(&PartitionIndexKey::ByMinValue(42, None), &PartitionRocksIndex::MinValue).unwrap();
+            assert_eq!(unbounded.into_iter().map(|p| p.get_id()).collect::<Vec<_>>(), vec![ids[1]]);
+            assert_eq!(
+                range_ids(PartitionIndexKey::ByMinValue(42, Some(row(20))), PartitionIndexKey::ByMinValue(42, Some(row(30)))),
+                vec![ids[3], child.get_id(), ids[0]]
+            );
+            assert!(partitions.check_index_integrity().unwrap().is_empty());
+        }
+        RocksMetaStore::cleanup_test_metastore("ordered_index_range_test");
+    }
+
+    #[actix_rt::test]
+    async fn unbounded_partitions_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("unbounded_partitions_test");
+        {
+            meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            let columns = vec![Column::new("col1".to_string(), ColumnType::Int, 0)];
+            let table = meta_store.create_table("foo".to_string(), "boo".to_string(), columns, None, None, vec![], vec![]).await.unwrap();
+            let index = meta_store.get_default_index(table.get_id()).await.unwrap();
+            let partition = meta_store.get_active_partitions_by_index_id(index.get_id()).await.unwrap()[0].clone();
+            // Freshly created global partition has no data yet
+            assert!(meta_store.get_unbounded_partitions(index.get_id()).await.unwrap().is_empty());
+
+            let row = |v: i64| Row::new(vec![TableValue::Int(v)]);
+            let unbounded = meta_store.create_partition(partition.get_row().child(partition.get_id())).await.unwrap();
+            let bounded = meta_store.create_partition(partition.get_row().child(partition.get_id())).await.unwrap();
+            meta_store.swap_active_partitions(
+                vec![partition.get_id()],
+                vec![unbounded.get_id(), bounded.get_id()],
+                vec![],
+                vec![(10, (None, None)), (20, (Some(row(5)), Some(row(19))))],
+                None,
+                None
+            ).await.unwrap();
+
+            let res = meta_store.get_unbounded_partitions(index.get_id()).await.unwrap();
+            assert_eq!(res.into_iter().map(|p| p.get_id()).collect::<Vec<_>>(), vec![unbounded.get_id()]);
+        }
+        RocksMetaStore::cleanup_test_metastore("unbounded_partitions_test");
+    }
+
+    #[actix_rt::test]
+    async fn partition_min_value_index_backfill_test() {
+        let (remote_fs, meta_store) = RocksMetaStore::prepare_test_metastore("partition_min_value_index_backfill_test");
+        {
+            meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            let columns = vec![Column::new("col1".to_string(), ColumnType::Int, 0)];
+            let table = meta_store.create_table("foo".to_string(), "boo".to_string(), columns, None, None, vec![], vec![]).await.unwrap();
+            let index = meta_store.get_default_index(table.get_id()).await.unwrap();
+            let partition = meta_store.get_active_partitions_by_index_id(index.get_id()).await.unwrap()[0].clone();
+            let row = |v: i64| Row::new(vec![TableValue::Int(v)]);
+            let unbounded = meta_store.create_partition(partition.get_row().child(partition.get_id())).await.unwrap();
+            let bounded = meta_store.create_partition(partition.get_row().child(partition.get_id())).await.unwrap();
+            meta_store.swap_active_partitions(
+                vec![partition.get_id()],
+                vec![unbounded.get_id(), bounded.get_id()],
+                vec![],
+                vec![(10, (None, None)), (20, (Some(row(5)), Some(row(19))))],
+                None,
+                None
+            ).await.unwrap();
+
+            // Strip the min value index to get the layout written before it existed
+            let path = {
+                let db = meta_store.db.read().await.clone();
+                let partitions = PartitionRocksTable::new(db.clone());
+                let index = &PartitionRocksIndex::MinValue;
+                for p in partitions.all_rows().unwrap() {
+                    let key = RowKey::SecondaryIndex(
+                        partitions.index_id(BaseRocksSecondaryIndex::<Partition>::get_id(index)),
+                        index.key_prefix(p.get_row()),
+                        p.get_id()
+                    );
+                    db.delete(key.to_bytes()).unwrap();
+                }
+                db.delete(migration_version_key()).unwrap();
+                db.path().to_path_buf()
+            };
+            assert!(meta_store.get_unbounded_partitions(index.get_id()).await.unwrap().is_empty());
+            drop(meta_store);
+
+            let meta_store = RocksMetaStore::new(path.clone(), remote_fs.clone()).unwrap();
+            let res = meta_store.get_unbounded_partitions(index.get_id()).await.unwrap();
+            assert_eq!(res.into_iter().map(|p| p.get_id()).collect::<Vec<_>>(), vec![unbounded.get_id()]);
+            let db = meta_store.db.read().await.clone();
+            let bounded_rows = PartitionRocksTable::new(db).get_rows_by_index_range(
+                &PartitionIndexKey::ByMinValue(index.get_id(), Some(row(0))),
+                &PartitionIndexKey::ByMinValue(index.get_id(), Some(row(10))),
+                &PartitionRocksIndex::MinValue
+            ).unwrap();
+            assert_eq!(bounded_rows.into_iter().map(|p| p.get_id()).collect::<Vec<_>>(), vec![bounded.get_id()]);
+            assert!(meta_store.check_integrity().await.unwrap().is_empty());
+
+            // Once marked as done the backfill isn't repeated on the next open
+            let db = meta_store.db.read().await.clone();
+            assert_eq!(RocksMetaStore::migration_version(&db).unwrap(), METASTORE_MIGRATION_VERSION);
+            let partitions = PartitionRocksTable::new(db.clone());
+            let index_key = RowKey::SecondaryIndex(
+                partitions.index_id(BaseRocksSecondaryIndex::<Partition>::get_id(&PartitionRocksIndex::MinValue)),
+                PartitionRocksIndex::MinValue.key_prefix(unbounded.get_row()),
+                unbounded.get_id()
+            );
+            db.delete(index_key.to_bytes()).unwrap();
+            drop(db);
+            drop(partitions);
+            drop(meta_store);
+
+            let meta_store = RocksMetaStore::new(path, remote_fs).unwrap();
+            assert!(meta_store.get_unbounded_partitions(index.get_id()).await.unwrap().is_empty());
+        }
+        RocksMetaStore::cleanup_test_metastore("partition_min_value_index_backfill_test");
+    }
+
+    #[actix_rt::test]
+    async fn active_partitions_for_range_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("active_partitions_for_range_test");
+        {
+            meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            let columns = vec![Column::new("col1".to_string(), ColumnType::Int, 0)];
+            let table = meta_store.create_table("foo".to_string(), "boo".to_string(), columns, None, None, vec![], vec![]).await.unwrap();
+            let index = meta_store.get_default_index(table.get_id()).await.unwrap();
+            let partition = meta_store.get_active_partitions_by_index_id(index.get_id()).await.unwrap()[0].clone();
+
+            let row = |v: i64| Row::new(vec![TableValue::Int(v)]);
+            let mut children = Vec::new();
+            for _ in 0..4 {
+                children.push(meta_store.create_partition(partition.get_row().child(partition.get_id())).await.unwrap().get_id());
+            }
+            meta_store.swap_active_partitions(
+                vec![partition.get_id()],
+                children.clone(),
+                vec![],
+                vec![
+                    (0, (None, Some(row(9)))),
+                    (0, (Some(row(10)), Some(row(19)))),
+                    (0, (Some(row(20)), Some(row(29)))),
+                    (0, (Some(row(30)), None)),
+                ],
+                None,
+                None
+            ).await.unwrap();
+
+            let for_range = |from: Option<Row>, to: Option<Row>| {
+                let meta_store = meta_store.clone();
+                let index_id = index.get_id();
+                async move {
+                    meta_store.get_active_partitions_for_range(index_id, from, to).await.unwrap()
+                        .into_iter().map(|p| p.get_id()).collect::<Vec<_>>()
+                }
+            };
+            assert_eq!(for_range(Some(row(12)), Some(row(25))).await, vec![children[1], children[2]]);
+            assert_eq!(for_range(Some(row(19)), Some(row(20))).await, vec![children[1], children[2]]);
+            assert_eq!(for_range(None, Some(row(5))).await, vec![children[0]]);
+            assert_eq!(for_range(Some(row(35)), None).await, vec![children[3]]);
+            assert_eq!(for_range(Some(row(100)), Some(row(200))).await, vec![children[3]]);
+            assert_eq!(for_range(None, None).await, children);
+        }
+        RocksMetaStore::cleanup_test_metastore("active_partitions_for_range_test");
+    }
+
+    #[actix_rt::test]
+    async fn swap_active_partitions_foreign_chunk_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("swap_active_partitions_foreign_chunk_test");
+        {
+            meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            let columns = vec![Column::new("col1".to_string(), ColumnType::Int, 0)];
+            let table = meta_store.create_table("foo".to_string(), "boo".to_string(), columns, None, None, vec![], vec![]).await.unwrap();
+            let index = meta_store.get_default_index(table.get_id()).await.unwrap();
+            let partition = meta_store.get_active_partitions_by_index_id(index.get_id()).await.unwrap()[0].clone();
+            let other_partition = meta_store.create_partition(Partition::new(index.get_id(), None, None)).await.unwrap();
+
+            let chunk = meta_store.create_chunk(partition.get_id(), 10).await.unwrap();
+            meta_store.chunk_uploaded(chunk.get_id()).await.unwrap();
+            let foreign_chunk = meta_store.create_chunk(other_partition.get_id(), 10).await.unwrap();
+            meta_store.chunk_uploaded(foreign_chunk.get_id()).await.unwrap();
+            let child = meta_store.create_partition(partition.get_row().child(partition.get_id())).await.unwrap();
+
+            let res = meta_store.swap_active_partitions(
+                vec![partition.get_id()],
+                vec![child.get_id()],
+                vec![chunk.get_id(), foreign_chunk.get_id()],
+                vec![(20, (None, None))],
+                None,
+                None
+            ).await;
+            assert!(res.is_err());
+            // Nothing of the rejected swap is applied
+            assert!(meta_store.get_partition(partition.get_id()).await.unwrap().get_row().is_active());
+            assert!(!meta_store.get_partition(child.get_id()).await.unwrap().get_row().is_active());
+            assert!(meta_store.get_chunk(chunk.get_id()).await.unwrap().get_row().active());
+            assert!(meta_store.get_chunk(foreign_chunk.get_id()).await.unwrap().get_row().active());
+
+            meta_store.swap_active_partitions(
+                vec![partition.get_id()],
+                vec![child.get_id()],
+                vec![chunk.get_id()],
+                vec![(10, (None, None))],
+                None,
+                None
+            ).await.unwrap();
+            assert!(!meta_store.get_chunk(chunk.get_id()).await.unwrap().get_row().active());
+            assert!(meta_store.get_chunk(foreign_chunk.get_id()).await.unwrap().get_row().active());
+        }
+        RocksMetaStore::cleanup_test_metastore("swap_active_partitions_foreign_chunk_test");
+    }
+
+    #[actix_rt::test]
+    async fn delete_partition_and_index_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("delete_partition_and_index_test");
+        {
+            meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            let columns = vec![Column::new("col1".to_string(), ColumnType::Int, 0), Column::new("col2".to_string(), ColumnType::Int, 1)];
+            let table = meta_store.create_table(
+                "foo".to_string(), "boo".to_string(), columns, None, None,
+                vec![IndexDef { name: "by_col2".to_string(), columns: vec!["col2".to_string()] }],
+                vec![]
+            ).await.unwrap();
+            let default_index = meta_store.get_default_index(table.get_id()).await.unwrap();
+            let by_col2 = meta_store.get_table_indexes(table.get_id()).await.unwrap()
+                .into_iter().find(|i| i.get_row().get_name() == "by_col2").unwrap();
+            let partition = meta_store.get_active_partitions_by_index_id(default_index.get_id()).await.unwrap()[0].clone();
+
+            let pending_chunk = meta_store.create_chunk(partition.get_id(), 10).await.unwrap();
+            let active_chunk = meta_store.create_chunk(partition.get_id(), 10).await.unwrap();
+            meta_store.chunk_uploaded(active_chunk.get_id()).await.unwrap();
+
+            let err = meta_store.delete_partition(partition.get_id()).await.unwrap_err();
+            assert_eq!(err.cause(), CubeErrorCause::User);
+            meta_store.get_chunk(active_chunk.get_id()).await.unwrap();
+
+            meta_store.deactivate_chunk(active_chunk.get_id()).await.unwrap();
+            meta_store.delete_partition(partition.get_id()).await.unwrap();
+            assert!(meta_store.get_partition(partition.get_id()).await.is_err());
+            assert!(meta_store.get_chunk(pending_chunk.get_id()).await.is_err());
+            assert!(meta_store.get_chunk(active_chunk.get_id()).await.is_err());
+
+            let col2_partition = meta_store.get_active_partitions_by_index_id(by_col2.get_id()).await.unwrap()[0].clone();
+            let col2_chunk = meta_store.create_chunk(col2_partition.get_id(), 10).await.unwrap();
+            meta_store.chunk_uploaded(col2_chunk.get_id()).await.unwrap();
+            meta_store.delete_index(by_col2.get_id(), false).await.unwrap();
+            assert!(meta_store.get_partition(col2_partition.get_id()).await.is_err());
+            assert!(meta_store.get_chunk(col2_chunk.get_id()).await.is_err());
+            assert_eq!(
+                meta_store.get_table_indexes(table.get_id()).await.unwrap().into_iter().map(|i| i.get_id()).collect::<Vec<_>>(),
+                vec![default_index.get_id()]
+            );
+
+            let err = meta_store.delete_index(default_index.get_id(), false).await.unwrap_err();
+            assert_eq!(err.cause(), CubeErrorCause::User);
+            meta_store.delete_index(default_index.get_id(), true).await.unwrap();
+            assert!(meta_store.get_table_indexes(table.get_id()).await.unwrap().is_empty());
+        }
+        RocksMetaStore::cleanup_test_metastore("delete_partition_and_index_test");
+    }
+
+    #[actix_rt::test]
+    async fn index_stats_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("index_stats_test");
+        {
+            meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            let columns = vec![Column::new("col1".to_string(), ColumnType::Int, 0), Column::new("col2".to_string(), ColumnType::String, 1)];
+            let table = meta_store.create_table("foo".to_string(), "boo".to_string(), columns, None, None, vec![], vec![]).await.unwrap();
+            let index = meta_store.get_default_index(table.get_id()).await.unwrap();
+            let partition = meta_store.get_active_partitions_by_index_id(index.get_id()).await.unwrap()[0].clone();
+            assert_eq!(meta_store.get_index_stats(index.get_id()).await.unwrap(), None);
+
+            let rows = vec![
+                Row::new(vec![TableValue::Int(3), TableValue::String("b".to_string())]),
+                Row::new(vec![TableValue::Int(7), TableValue::Null]),
+            ];
+            let child = meta_store.create_partition(partition.get_row().child(partition.get_id())).await.unwrap();
+            meta_store.swap_active_partitions(
+                vec![partition.get_id()],
+                vec![child.get_id()],
+                vec![],
+                vec![(2, (None, None))],
+                Some(IndexStats::from_rows(index.get_id(), 2, &rows)),
+                None
+            ).await.unwrap();
+            let stats = meta_store.get_index_stats(index.get_id()).await.unwrap().unwrap();
+            assert_eq!(stats.get_row().columns(), &vec![
+                ColumnStats::new(Some(TableValue::Int(3)), Some(TableValue::Int(7)), 0),
+                ColumnStats::new(Some(TableValue::String("b".to_string())), Some(TableValue::String("b".to_string())), 1),
+            ]);
+
+            let rows = vec![
+                Row::new(vec![TableValue::Null, TableValue::String("a".to_string())]),
+                Row::new(vec![TableValue::Int(10), TableValue::Null]),
+            ];
+            let grandchild = meta_store.create_partition(child.get_row().child(child.get_id())).await.unwrap();
+            meta_store.swap_active_partitions(
+                vec![child.get_id()],
+                vec![grandchild.get_id()],
+                vec![],
+                vec![(4, (None, None))],
+                Some(IndexStats::from_rows(index.get_id(), 2, &rows)),
+                None
+            ).await.unwrap();
+            let merged = meta_store.get_index_stats(index.get_id()).await.unwrap().unwrap();
+            assert_eq!(merged.get_id(), stats.get_id());
+            assert_eq!(merged.get_row().columns(), &vec![
+                ColumnStats::new(Some(TableValue::Int(3)), Some(TableValue::Int(10)), 1),
+                ColumnStats::new(Some(TableValue::String("a".to_string())), Some(TableValue::String("b".to_string())), 2),
+            ]);
+
+            meta_store.drop_table(table.get_id(), false).await.unwrap();
+            assert_eq!(meta_store.get_index_stats(index.get_id()).await.unwrap(), None);
+        }
+        RocksMetaStore::cleanup_test_metastore("index_stats_test");
+    }
+
+    #[actix_rt::test]
+    async fn meta_store_stats_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("meta_store_stats_test");
+        {
+            meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            let columns = vec![Column::new("col1".to_string(), ColumnType::Int, 0), Column::new("col2".to_string(), ColumnType::Int, 1)];
+            let table = meta_store.create_table(
+                "foo".to_string(), "boo".to_string(), columns, None, None,
+                vec![IndexDef { name: "by_col2".to_string(), columns: vec!["col2".to_string()] }],
+                vec![]
+            ).await.unwrap();
+            let index = meta_store.get_default_index(table.get_id()).await.unwrap();
+            let partition = meta_store.get_active_partitions_by_index_id(index.get_id()).await.unwrap()[0].clone();
+            meta_store.create_chunk(partition.get_id(), 10).await.unwrap();
+            meta_store.create_chunk(partition.get_id(), 10).await.unwrap();
+            meta_store.create_wal(table.get_id(), 10).await.unwrap();
+
+            let stats = meta_store.get_meta_store_stats().await.unwrap();
+            let expected = vec![
+                (TableId::Schemas, 1),
+                (TableId::Tables, 1),
+                (TableId::Indexes, 2),
+                (TableId::Partitions, 2),
+                (TableId::Chunks, 2),
+                (TableId::WALs, 1),
+                (TableId::Jobs, 0),
+                (TableId::IndexStats, 0)
+            ].into_iter().collect::<HashMap<_, _>>();
+            assert_eq!(stats.row_counts, expected);
+            assert_eq!(stats.latest_sequence_number, meta_store.db.read().await.latest_sequence_number());
+            assert!(stats.pending_updates > 0);
+
+            meta_store.run_upload().await.unwrap();
+            let stats = meta_store.get_meta_store_stats().await.unwrap();
+            assert_eq!(stats.last_upload_seq, stats.latest_sequence_number);
+            assert_eq!(stats.last_check_seq, stats.latest_sequence_number);
+            assert_eq!(stats.pending_updates, 0);
+        }
+        RocksMetaStore::cleanup_test_metastore("meta_store_stats_test");
+    }
+
+    #[actix_rt::test]
+    async fn stream_active_partitions_by_index_id_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("stream_active_partitions_by_index_id_test");
+        {
+            meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            let columns = vec![Column::new("col1".to_string(), ColumnType::Int, 0)];
+            let row = |v: i64| Some(Row::new(vec![TableValue::Int(v)]));
+            let bounds = (0..3000).map(|i| (row(i * 10), row(i * 10 + 10))).collect::<Vec<_>>();
+            let table = meta_store.create_table("foo".to_string(), "boo".to_string(), columns, None, None, vec![], bounds).await.unwrap();
+            let index = meta_store.get_default_index(table.get_id()).await.unwrap();
+            let partitions = meta_store.get_active_partitions_by_index_id(index.get_id()).await.unwrap();
+            meta_store.swap_active_partitions(
+                vec![partitions[0].get_id(), partitions[1].get_id()], vec![], vec![], vec![], None, None
+            ).await.unwrap();
+
+            let expected = meta_store.get_active_partitions_by_index_id(index.get_id()).await.unwrap();
+            assert_eq!(expected.len(), 2998);
+            let mut receiver = meta_store.stream_active_partitions_by_index_id(index.get_id()).await.unwrap();
+            let mut streamed = Vec::new();
+            while let Some(partition) = receiver.recv().await {
+                streamed.push(partition.unwrap());
+            }
+            assert_eq!(streamed, expected);
+
+            let mut receiver = meta_store.stream_active_partitions_by_index_id(index.get_id() + 100).await.unwrap();
+            assert!(receiver.recv().await.is_none());
+        }
+        RocksMetaStore::cleanup_test_metastore("stream_active_partitions_by_index_id_test");
+    }
+
+    #[actix_rt::test]
+    async fn referenced_remote_files_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("referenced_remote_files_test");
+        {
+            meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            let columns = vec![Column::new("col1".to_string(), ColumnType::Int, 0)];
+            let table = meta_store.create_table("foo".to_string(), "boo".to_string(), columns, None, None, vec![], vec![]).await.unwrap();
+            let index = meta_store.get_default_index(table.get_id()).await.unwrap();
+            let root = meta_store.get_active_partitions_by_index_id(index.get_id()).await.unwrap()[0].clone();
+            let root_chunk = meta_store.create_chunk(root.get_id(), 10).await.unwrap();
+            meta_store.chunk_uploaded(root_chunk.get_id()).await.unwrap();
+            // Root partition has no file of its own
+            assert_eq!(meta_store.get_referenced_remote_files().await.unwrap(), vec![format!("{}.chunk.parquet", root_chunk.get_id())].into_iter().collect::<HashSet<_>>());
+
+            let active_child = meta_store.create_partition(root.get_row().child(root.get_id())).await.unwrap();
+            let inactive_child = meta_store.create_partition(root.get_row().child(root.get_id())).await.unwrap();
+            meta_store.swap_active_partitions(
+                vec![root.get_id()], vec![active_child.get_id()], vec![root_chunk.get_id()], vec![(10, (None, None))], None, None
+            ).await.unwrap();
+
+            let uploaded = meta_store.create_chunk(active_child.get_id(), 10).await.unwrap();
+            meta_store.chunk_uploaded(uploaded.get_id()).await.unwrap();
+            meta_store.create_chunk(active_child.get_id(), 10).await.unwrap();
+            let deactivated = meta_store.create_chunk(active_child.get_id(), 10).await.unwrap();
+            meta_store.chunk_uploaded(deactivated.get_id()).await.unwrap();
+            meta_store.deactivate_chunk(deactivated.get_id()).await.unwrap();
+            meta_store.create_chunk(inactive_child.get_id(), 10).await.unwrap();
+
+            let expected = vec![
+                format!("{}.parquet", active_child.get_id()),
+                format!("{}.chunk.parquet", uploaded.get_id()),
+            ].into_iter().collect::<HashSet<_>>();
+            assert_eq!(meta_store.get_referenced_remote_files().await.unwrap(), expected);
+        }
+        RocksMetaStore::cleanup_test_metastore("referenced_remote_files_test");
+    }
+
+    #[actix_rt::test]
+    async fn get_chunks_by_partitions_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("get_chunks_by_partitions_test");
+        {
+            meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            let columns = vec![Column::new("col1".to_string(), ColumnType::Int, 0)];
+            let table = meta_store.create_table("foo".to_string(), "boo".to_string(), columns, None, None, vec![], vec![]).await.unwrap();
+            let index = meta_store.get_default_index(table.get_id()).await.unwrap();
+            let first = meta_store.get_active_partitions_by_index_id(index.get_id()).await.unwrap()[0].clone();
+            let second = meta_store.create_partition(Partition::new(index.get_id(), None, None)).await.unwrap();
+            let empty = meta_store.create_partition(Partition::new(index.get_id(), None, None)).await.unwrap();
+            let other = meta_store.create_partition(Partition::new(index.get_id(), None, None)).await.unwrap();
+
+            let uploaded = |partition_id: u64| {
+                let meta_store = meta_store.clone();
+                async move {
+                    let chunk = meta_store.create_chunk(partition_id, 10).await.unwrap();
+                    meta_store.chunk_uploaded(chunk.get_id()).await.unwrap().get_id()
+                }
+            };
+            let first_chunks = vec![uploaded(first.get_id()).await, uploaded(first.get_id()).await];
+            let second_chunk = uploaded(second.get_id()).await;
+            uploaded(other.get_id()).await;
+            // Neither pending nor deactivated chunks are returned
+            meta_store.create_chunk(second.get_id(), 10).await.unwrap();
+            let deactivated = uploaded(second.get_id()).await;
+            meta_store.deactivate_chunk(deactivated).await.unwrap();
+
+            let grouped = meta_store.get_chunks_by_partitions(vec![first.get_id(), second.get_id(), empty.get_id()]).await.unwrap();
+            let ids = |partition_id: u64| grouped.get(&partition_id).unwrap().iter().map(|c| c.get_id()).collect::<Vec<_>>();
+            assert_eq!(grouped.len(), 3);
+            assert_eq!(ids(first.get_id()), first_chunks);
+            assert_eq!(ids(second.get_id()), vec![second_chunk]);
+            assert_eq!(ids(empty.get_id()), Vec::<u64>::new());
+        }
+        RocksMetaStore::cleanup_test_metastore("get_chunks_by_partitions_test");
+    }
+
+    #[actix_rt::test]
+    async fn write_operation_panic_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("write_operation_panic_test");
+        {
+            let err = meta_store.write_operation::<_, ()>(|db_ref, batch_pipe| {
+                SchemaRocksTable::new(db_ref).insert(Schema { name: "lost".to_string() }, batch_pipe)?;
+                panic!("Write failed half way through");
+            }).await.unwrap_err();
+            assert_eq!(err.cause(), CubeErrorCause::Internal);
+            assert_eq!(err.message, "metastore write task panicked");
+
+            let (sender, _receiver) = tokio::sync::broadcast::channel(100);
+            meta_store.add_listener(sender).await;
+            meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            assert!(meta_store.get_schema("lost".to_string()).await.is_err());
+            assert_eq!(meta_store.get_schemas().await.unwrap().len(), 1);
+        }
+        RocksMetaStore::cleanup_test_metastore("write_operation_panic_test");
+    }
+
+    #[actix_rt::test]
+    async fn write_concurrency_limit_test() {
+        let store_path = env::current_dir().unwrap().join("test-write_concurrency_limit_test-local");
+        let remote_store_path = env::current_dir().unwrap().join("test-write_concurrency_limit_test-remote");
+        let _ = fs::remove_dir_all(store_path.clone());
+        let _ = fs::remove_dir_all(remote_store_path.clone());
+        {
+            let remote_fs = LocalDirRemoteFs::new(remote_store_path.clone(), store_path.clone());
+            let config = MetaStoreConfig { max_concurrent_writes: Some(2), ..MetaStoreConfig::default() };
+            let meta_store = RocksMetaStore::with_listener(store_path.join("metastore").as_path(), vec![], remote_fs, config).unwrap();
+
+            let running = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+            let max_running = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+            let writes = (0..20).map(|i| {
+                let meta_store = meta_store.clone();
+                let running = running.clone();
+                let max_running = max_running.clone();
+                tokio::spawn(async move {
+                    meta_store.write_operation(move |db_ref, batch_pipe| {
+                        let now_running = running.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+                        max_running.fetch_max(now_running, std::sync::atomic::Ordering::SeqCst);
+                        std::thread::sleep(Duration::from_millis(20));
+                        let schemas = SchemaRocksTable::new(db_ref);
+                        let res = schemas.insert(Schema { name: format!("foo{}", i) }, batch_pipe);
+                        running.fetch_sub(1, std::sync::atomic::Ordering::SeqCst);
+                        res
+                    }).await
+                })
+            }).collect::<Vec<_>>();
+            for res in join_all(writes).await.into_iter() {
+                res.unwrap().unwrap();
+            }
+
+            assert_eq!(meta_store.get_schemas().await.unwrap().len(), 20);
+            let max_running = max_running.load(std::sync::atomic::Ordering::SeqCst);
+            assert!(max_running <= 2, "{} writes ran concurrently", max_running);
+            assert_eq!(meta_store.write_semaphore.as_ref().unwrap().available_permits(), 2);
+        }
+        {
+            let remote_fs = LocalDirRemoteFs::new(remote_store_path.clone(), store_path.clone());
+            let config = MetaStoreConfig { max_concurrent_writes: Some(0), ..MetaStoreConfig::default() };
+            let err = RocksMetaStore::with_listener(store_path.join("metastore-zero").as_path(), vec![], remote_fs, config).err().unwrap();
+            assert_eq!(err.cause(), CubeErrorCause::User);
+        }
+        let _ = fs::remove_dir_all(store_path);
+        let _ = fs::remove_dir_all(remote_store_path);
+    }
+
+    #[actix_rt::test]
+    async fn default_index_record_batch_schema_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("default_index_record_batch_schema_test");
+        {
+            meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            let columns = vec![
+                Column::new("name".to_string(), ColumnType::String, 0),
+                Column::new("count".to_string(), ColumnType::Int, 1),
+                Column::new("data".to_string(), ColumnType::Bytes, 2),
+                Column::new("created".to_string(), ColumnType::Timestamp, 3),
+                Column::new("flag".to_string(), ColumnType::Boolean, 4),
+                Column::new("props".to_string(), ColumnType::Json, 5),
+                Column::new("ratio".to_string(), ColumnType::Float, 6),
+            ];
+            let table = meta_store.create_table(
+                "foo".to_string(), "boo".to_string(), columns, None, None,
+                vec![IndexDef { name: "by_count".to_string(), columns: vec!["count".to_string()] }],
+                vec![]
+            ).await.unwrap();
+            let schema = meta_store.get_default_index_record_batch_schema(table.get_id()).await.unwrap();
+            assert_eq!(
+                schema.fields().iter().map(|f| (f.name().to_string(), f.data_type().clone())).collect::<Vec<_>>(),
+                vec![
+                    ("name".to_string(), DataType::Utf8),
+                    ("count".to_string(), DataType::Int64),
+                    ("data".to_string(), DataType::Binary),
+                    ("created".to_string(), DataType::Timestamp(Microsecond, None)),
+                    ("flag".to_string(), DataType::Boolean),
+                    ("props".to_string(), DataType::Utf8),
+                    ("ratio".to_string(), DataType::Float64),
+                ]
+            );
+
+            let decimal_table = meta_store.create_table(
+                "foo".to_string(), "decimal".to_string(), vec![Column::new("amount".to_string(), ColumnType::Decimal, 0)], None, None, vec![], vec![]
+            ).await.unwrap();
+            let err = meta_store.get_default_index_record_batch_schema(decimal_table.get_id()).await.unwrap_err();
+            assert_eq!(err.cause(), CubeErrorCause::User);
+        }
+        RocksMetaStore::cleanup_test_metastore("default_index_record_batch_schema_test");
+    }
+
+    #[actix_rt::test]
+    async fn wait_for_table_jobs_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("wait_for_table_jobs_test");
+        {
+            meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            let columns = vec![Column::new("col1".to_string(), ColumnType::Int, 0)];
+            let table = meta_store.create_table("foo".to_string(), "boo".to_string(), columns.clone(), None, None, vec![], vec![]).await.unwrap();
+            let other_table = meta_store.create_table("foo".to_string(), "other".to_string(), columns, None, None, vec![], vec![]).await.unwrap();
+            let index = meta_store.get_default_index(table.get_id()).await.unwrap();
+            let partition = meta_store.get_active_partitions_by_index_id(index.get_id()).await.unwrap()[0].clone();
+
+            meta_store.wait_for_table_jobs(table.get_id(), Duration::from_millis(100)).await.unwrap();
+
+            let job = meta_store.add_job(Job::new(
+                RowKey::Table(TableId::Partitions, partition.get_id()),
+                JobType::PartitionCompaction,
+                "localhost".to_string()
+            )).await.unwrap().unwrap();
+            meta_store.add_job(Job::new(
+                RowKey::Table(TableId::Tables, other_table.get_id()),
+                JobType::TableImport,
+                "localhost".to_string()
+            )).await.unwrap().unwrap();
+            assert_eq!(
+                meta_store.get_jobs_for_table(table.get_id()).await.unwrap().iter().map(|j| j.get_id()).collect::<Vec<_>>(),
+                vec![job.get_id()]
+            );
+
+            assert!(meta_store.wait_for_table_jobs(table.get_id(), Duration::from_millis(200)).await.is_err());
+
+            let meta_store_to_complete = meta_store.clone();
+            let job_id = job.get_id();
+            tokio::spawn(async move {
+                tokio::time::delay_for(Duration::from_millis(300)).await;
+                meta_store_to_complete.delete_job(job_id).await.unwrap();
+            });
+            let start = Instant::now();
+            meta_store.wait_for_table_jobs(table.get_id(), Duration::from_secs(10)).await.unwrap();
+            assert!(start.elapsed() >= Duration::from_millis(300));
+            assert!(meta_store.get_jobs_for_table(table.get_id()).await.unwrap().is_empty());
+        }
+        RocksMetaStore::cleanup_test_metastore("wait_for_table_jobs_test");
+    }
+
+    #[actix_rt::test]
+    async fn create_table_spec_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("create_table_spec_test");
+        {
+            meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            let columns = vec![
+                Column::new("col1".to_string(), ColumnType::Int, 0),
+                Column::new("col2".to_string(), ColumnType::Decimal, 1),
+                Column::new("col3".to_string(), ColumnType::Timestamp, 2),
+            ];
+            let index_defs = vec![IndexDef { name: "by_col3".to_string(), columns: vec!["col3".to_string()] }];
+            let table = meta_store.create_table(
+                "foo".to_string(),
+                "boo".to_string(),
+                columns.clone(),
+                Some("file:///tmp/boo.csv".to_string()),
+                Some(ImportFormat::CSV),
+                index_defs.clone(),
+                vec![]
+            ).await.unwrap();
+
+            let spec = meta_store.get_create_table_spec(table.get_id()).await.unwrap();
+            assert_eq!(spec, CreateTableSpec {
+                schema_name: "foo".to_string(),
+                table_name: "boo".to_string(),
+                columns,
+                location: Some("file:///tmp/boo.csv".to_string()),
+                import_format: Some(ImportFormat::CSV),
+                indexes: index_defs
+            });
+
+            meta_store.create_schema("bar".to_string(), false).await.unwrap();
+            let recreated = meta_store.create_table(
+                "bar".to_string(), spec.table_name.clone(), spec.columns.clone(), spec.location.clone(), spec.import_format.clone(), spec.indexes.clone(), vec![]
+            ).await.unwrap();
+            let recreated_spec = meta_store.get_create_table_spec(recreated.get_id()).await.unwrap();
+            assert_eq!(recreated_spec, CreateTableSpec { schema_name: "bar".to_string(), ..spec });
+
+            assert!(meta_store.get_create_table_spec(table.get_id() + 100).await.is_err());
+        }
+        RocksMetaStore::cleanup_test_metastore("create_table_spec_test");
+    }
+
+    #[actix_rt::test]
+    async fn table_index_defs_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("table_index_defs_test");
+        {
+            meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            let columns = vec![
+                Column::new("col1".to_string(), ColumnType::Int, 0),
+                Column::new("col2".to_string(), ColumnType::String, 1),
+                Column::new("col3".to_string(), ColumnType::Int, 2),
+            ];
+            let index_defs = vec![
+                IndexDef { name: "by_col3_col2".to_string(), columns: vec!["col3".to_string(), "col2".to_string()] },
+                IndexDef { name: "by_col1".to_string(), columns: vec!["col1".to_string()] },
+            ];
+            let table = meta_store.create_table("foo".to_string(), "boo".to_string(), columns, None, None, index_defs.clone(), vec![]).await.unwrap();
+
+            let mut defs = meta_store.get_table_index_defs(table.get_id()).await.unwrap();
+            defs.sort_by(|a, b| a.name.cmp(&b.name));
+            let mut expected = index_defs.clone();
+            expected.sort_by(|a, b| a.name.cmp(&b.name));
+            // Sort columns follow table column order
+            expected[1].columns = vec!["col2".to_string(), "col3".to_string()];
+            assert_eq!(defs, expected);
+
+            let default_index = meta_store.get_default_index(table.get_id()).await.unwrap();
+            assert_eq!(default_index.get_row().to_index_def().columns, vec!["col1".to_string(), "col2".to_string(), "col3".to_string()]);
+        }
+        RocksMetaStore::cleanup_test_metastore("table_index_defs_test");
+    }
+
+    #[actix_rt::test]
+    async fn float_column_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("float_column_test");
+        {
+            meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            let columns = vec![
+                Column::new("sensor".to_string(), ColumnType::String, 0),
+                Column::new("reading".to_string(), ColumnType::Float, 1),
+            ];
+            meta_store.create_table("foo".to_string(), "readings".to_string(), columns.clone(), None, None, vec![], vec![]).await.unwrap();
+
+            let table = meta_store.get_table("foo".to_string(), "readings".to_string()).await.unwrap();
+            let column = table.get_row().get_columns()[1].clone();
+            assert_eq!(column.get_column_type(), &ColumnType::Float);
+            assert_eq!(column.to_string(), "reading FLOAT");
+
+            let parquet_type = parquet::schema::types::Type::from(&column);
+            assert_eq!(parquet_type.get_physical_type(), Type::DOUBLE);
+
+            let field = column.arrow_field().unwrap();
+            assert_eq!(field.name(), "reading");
+            assert_eq!(field.data_type(), &DataType::Float64);
+
+            let decimal = Column::new("price".to_string(), ColumnType::Decimal, 2);
+            assert!(decimal.arrow_field().is_err());
+        }
+        RocksMetaStore::cleanup_test_metastore("float_column_test");
+    }
+
+    #[actix_rt::test]
+    async fn get_all_partitions_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("get_all_partitions_test");
+        {
+            meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            let columns = vec![Column::new("col1".to_string(), ColumnType::Int, 0), Column::new("col2".to_string(), ColumnType::Int, 1)];
+            let table = meta_store.create_table(
+                "foo".to_string(),
+                "boo".to_string(),
+                columns,
+                None,
+                None,
+                vec![IndexDef { name: "by_col2".to_string(), columns: vec!["col2".to_string()] }],
+                vec![]
+            ).await.unwrap();
+            let indexes = meta_store.get_table_indexes(table.get_id()).await.unwrap();
+            assert_eq!(indexes.len(), 2);
+            let partition = meta_store.get_active_partitions_by_index_id(indexes[0].get_id()).await.unwrap()[0].clone();
+            let other_partition = meta_store.get_active_partitions_by_index_id(indexes[1].get_id()).await.unwrap()[0].clone();
+            let child = meta_store.create_partition(partition.get_row().child(partition.get_id())).await.unwrap();
+
+            let all_ids = meta_store.get_all_partitions(false).await.unwrap().into_iter().map(|p| p.get_id()).collect::<Vec<_>>();
+            assert_eq!(all_ids, vec![partition.get_id(), other_partition.get_id(), child.get_id()]);
+            let active_ids = meta_store.get_all_partitions(true).await.unwrap().into_iter().map(|p| p.get_id()).collect::<Vec<_>>();
+            assert_eq!(active_ids, vec![partition.get_id(), other_partition.get_id()]);
+
+            meta_store.swap_active_partitions(vec![partition.get_id()], vec![child.get_id()], vec![], vec![(0, (None, None))], None, None).await.unwrap();
+            let active_ids = meta_store.get_all_partitions(true).await.unwrap().into_iter().map(|p| p.get_id()).collect::<Vec<_>>();
+            assert_eq!(active_ids, vec![other_partition.get_id(), child.get_id()]);
+            assert_eq!(meta_store.get_all_partitions(false).await.unwrap().len(), 3);
+        }
+        RocksMetaStore::cleanup_test_metastore("get_all_partitions_test");
+    }
+
+    #[actix_rt::test]
+    async fn listener_dropped_counts_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("listener_dropped_counts_test");
+        {
+            let (live_sender, _live_receiver) = tokio::sync::broadcast::channel(100);
+            let (dead_sender, dead_receiver) = tokio::sync::broadcast::channel(100);
+            drop(dead_receiver);
+            meta_store.add_listener(live_sender).await;
+            meta_store.add_listener(dead_sender).await;
+
+            meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            assert_eq!(meta_store.listener_dropped_counts().await, vec![0, 1]);
+
+            meta_store.rename_schema("foo".to_string(), "bar".to_string()).await.unwrap();
+            meta_store.delete_schema("bar".to_string()).await.unwrap();
+            assert_eq!(meta_store.listener_dropped_counts().await, vec![0, 4]);
+            assert!(meta_store.get_schemas().await.unwrap().is_empty());
+        }
+        RocksMetaStore::cleanup_test_metastore("listener_dropped_counts_test");
+    }
+
+    #[actix_rt::test]
+    async fn follower_loop_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("follower_loop_test");
+        let remote_path = env::current_dir().unwrap().join("test-follower_loop_test-remote");
+        let follower_path = env::current_dir().unwrap().join("test-follower_loop_test-follower");
+        let _ = fs::remove_dir_all(follower_path.clone());
+        {
+            meta_store.upload_check_point().await.unwrap();
+            meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            meta_store.run_upload().await.unwrap();
+
+            let follower_fs = LocalDirRemoteFs::new(remote_path.clone(), follower_path.clone());
+            let follower = RocksMetaStore::load_follower(
+                follower_path.join("metastore").as_path(), follower_fs, MetaStoreConfig::default()
+            ).await.unwrap();
+            follower.get_schema("foo".to_string()).await.unwrap();
+            assert_eq!(follower.pull_remote_logs().await.unwrap(), 0);
+
+            let follower_to_run = follower.clone();
+            let follower_loop = tokio::spawn(async move { follower_to_run.run_follower_loop().await });
+
+            meta_store.create_schema("bar".to_string(), false).await.unwrap();
+            meta_store.run_upload().await.unwrap();
+            let mut attempts = 0;
+            while follower.get_schema("bar".to_string()).await.is_err() {
+                attempts += 1;
+                assert!(attempts < 50, "Follower didn't catch up with primary");
+                tokio::time::delay_for(Duration::from_millis(100)).await;
+            }
+            assert_eq!(follower.get_schemas().await.unwrap().len(), 2);
+
+            follower.stop_processing_loops().await;
+            follower_loop.await.unwrap();
+
+            // Follower switches to the new checkpoint logs
+            meta_store.upload_check_point().await.unwrap();
+            meta_store.create_schema("baz".to_string(), false).await.unwrap();
+            meta_store.run_upload().await.unwrap();
+            assert_eq!(follower.pull_remote_logs().await.unwrap(), 1);
+            assert_eq!(follower.get_schemas().await.unwrap().len(), 3);
+            assert_eq!(follower.pull_remote_logs().await.unwrap(), 0);
+        }
+        {
+            // Restarted follower resumes from the persisted state
+            let follower_fs = LocalDirRemoteFs::new(remote_path, follower_path.clone());
+            let follower = RocksMetaStore::load_follower(
+                follower_path.join("metastore").as_path(), follower_fs, MetaStoreConfig::default()
+            ).await.unwrap();
+            assert_eq!(follower.pull_remote_logs().await.unwrap(), 0);
+            assert_eq!(follower.get_schemas().await.unwrap().len(), 3);
+
+            meta_store.create_schema("qux".to_string(), false).await.unwrap();
+            meta_store.run_upload().await.unwrap();
+            assert_eq!(follower.pull_remote_logs().await.unwrap(), 1);
+            assert_eq!(follower.get_schemas().await.unwrap().len(), 4);
+        }
+        let _ = fs::remove_dir_all(follower_path);
+        RocksMetaStore::cleanup_test_metastore("follower_loop_test");
+    }
+
+    #[actix_rt::test]
+    async fn follower_read_only_test() {
+        let (_, meta_store) = RocksMetaStore::prepare_test_metastore("follower_read_only_test");
+        let remote_path = env::current_dir().unwrap().join("test-follower_read_only_test-remote");
+        let follower_path = env::current_dir().unwrap().join("test-follower_read_only_test-follower");
+        let _ = fs::remove_dir_all(follower_path.clone());
+        {
+            meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            meta_store.upload_check_point().await.unwrap();
+
+            let follower_fs = LocalDirRemoteFs::new(remote_path, follower_path.clone());
+            let follower = RocksMetaStore::load_follower(
+                follower_path.join("metastore").as_path(), follower_fs, MetaStoreConfig::default()
+            ).await.unwrap();
+            let err = follower.create_schema("bar".to_string(), false).await.unwrap_err();
+            assert_eq!(err.cause(), CubeErrorCause::User);
+            assert_eq!(follower.run_upload().await.unwrap_err().cause(), CubeErrorCause::User);
+            assert_eq!(follower.run_upload_loop().await.unwrap_err().cause(), CubeErrorCause::User);
+            assert_eq!(follower.get_schemas().await.unwrap().len(), 1);
+
+            // Logs of the primary still apply
+            meta_store.create_schema("bar".to_string(), false).await.unwrap();
+            meta_store.run_upload().await.unwrap();
+            assert_eq!(follower.pull_remote_logs().await.unwrap(), 1);
+            assert_eq!(follower.get_schemas().await.unwrap().len(), 2);
+        }
+        let _ = fs::remove_dir_all(follower_path);
+        RocksMetaStore::cleanup_test_metastore("follower_read_only_test");
+    }
+
+    #[actix_rt::test]
+    async fn double_open_test() {
+        let (remote_fs, meta_store) = RocksMetaStore::prepare_test_metastore("double_open_test");
+        meta_store.create_schema("foo".to_string(), false).await.unwrap();
+
+        let path = env::current_dir().unwrap().join("test-double_open_test-local").join("metastore");
+        match RocksMetaStore::new(path.as_path(), remote_fs.clone()) {
+            Err(e) => assert!(e.message.contains("Can't open metastore"), "Unexpected error: {}", e),
+            Ok(_) => panic!("Second open of the same metastore path should fail")
+        }
+        meta_store.get_schema("foo".to_string()).await.unwrap();
+
+        drop(meta_store);
+        RocksMetaStore::cleanup_test_metastore("double_open_test");
+    }
+
+    #[actix_rt::test]
+    async fn snapshot_retention_test() {
+        let store_path = env::current_dir().unwrap().join("test-snapshot_retention_test-local");
+        {
+            let config = MetaStoreConfig { snapshot_retention: Duration::from_secs(60 * 60), ..MetaStoreConfig::default() };
+            let (remote_fs, meta_store) = RocksMetaStore::prepare_test_metastore_with_config("snapshot_retention_test", config);
+
+            let now_millis = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_millis();
+            let old_snapshot = format!("metastore-{}/CURRENT", now_millis - 10 * 60 * 1000);
+            fs::write(remote_fs.local_file(&old_snapshot).await.unwrap(), "old").unwrap();
+            remote_fs.upload_file(&old_snapshot).await.unwrap();
+
+            meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            meta_store.upload_check_point().await.unwrap();
+            assert!(remote_fs.list(&old_snapshot).await.unwrap().len() == 1);
+
+            // Checkpoint directories are named after the current millisecond
+            tokio::time::delay_for(Duration::from_millis(10)).await;
+            let default_meta_store = RocksMetaStore::new(store_path.join("metastore-default").as_path(), remote_fs.clone()).unwrap();
+            default_meta_store.upload_check_point().await.unwrap();
+            assert!(remote_fs.list(&old_snapshot).await.unwrap().is_empty());
+        }
+        RocksMetaStore::cleanup_test_metastore("snapshot_retention_test");
+    }
+
+    #[actix_rt::test]
+    async fn test_clock_checkpoint_test() {
+        {
+            let clock = Arc::new(TestClock::new(SystemTime::now()));
+            let config = MetaStoreConfig {
+                checkpoint_interval: Duration::from_secs(60),
+                snapshot_retention: Duration::from_secs(30),
+                clock: clock.clone(),
+                ..MetaStoreConfig::default()
+            };
+            let (remote_fs, meta_store) = RocksMetaStore::prepare_test_metastore_with_config("test_clock_checkpoint_test", config);
+            meta_store.upload_check_point().await.unwrap();
+            let first_checkpoint = meta_store.last_checkpoint_time.read().await.clone();
+            assert_eq!(first_checkpoint, clock.now());
+            let first_checkpoint_path = RocksMetaStore::meta_store_path(&first_checkpoint);
+
+            clock.advance(Duration::from_secs(59));
+            meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            meta_store.run_upload().await.unwrap();
+            assert_eq!(*meta_store.last_checkpoint_time.read().await, first_checkpoint);
+
+            clock.advance(Duration::from_secs(2));
+            meta_store.create_schema("bar".to_string(), false).await.unwrap();
+            meta_store.run_upload().await.unwrap();
+            assert_eq!(*meta_store.last_checkpoint_time.read().await, clock.now());
+
+            // The first checkpoint is now older than the retention according to the test clock
+            assert!(remote_fs.list(&format!("{}/", first_checkpoint_path)).await.unwrap().is_empty());
+            assert!(!remote_fs.list(&format!("{}/", RocksMetaStore::meta_store_path(&clock.now()))).await.unwrap().is_empty());
+        }
+        RocksMetaStore::cleanup_test_metastore("test_clock_checkpoint_test");
+    }
+
+    #[actix_rt::test]
+    async fn error_cause_test() {
+        let (remote_fs, meta_store) = RocksMetaStore::prepare_test_metastore("error_cause_test");
+        {
+            let schema = meta_store.create_schema("foo".to_string(), false).await.unwrap();
+
+            let err = meta_store.get_schema_by_id(schema.get_id() + 1).await.unwrap_err();
+            assert_eq!(err.cause(), CubeErrorCause::NotFound);
+            let err = meta_store.get_schema("bar".to_string()).await.unwrap_err();
+            assert_eq!(err.cause(), CubeErrorCause::NotFound);
+
+            let err = meta_store.create_schema("foo".to_string(), false).await.unwrap_err();
+            assert_eq!(err.cause(), CubeErrorCause::UniqueViolation);
+
+            let db = meta_store.db.read().await.clone();
+            let schemas = SchemaRocksTable::new(db.clone());
+            for index_row in schemas.insert_index_row(&Schema { name: "baz".to_string() }, 999).unwrap() {
+                db.put(index_row.key, index_row.val).unwrap();
+            }
+            let err = schemas.get_rows_by_index(&"baz".to_string(), &SchemaRocksIndex::Name).unwrap_err();
+            assert_eq!(err.cause(), CubeErrorCause::Corruption);
+
+            let err = remote_fs.download_file("missing/file.chunk.parquet").await.unwrap_err();
+            assert_eq!(err.cause(), CubeErrorCause::RemoteStorage);
+        }
+        RocksMetaStore::cleanup_test_metastore("error_cause_test");
+    }
+
+    #[actix_rt::test]
+    async fn compressed_log_round_trip_test() {
+        let dir = env::current_dir().unwrap().join("test-compressed_log_round_trip_test");
+        let _ = fs::remove_dir_all(dir.clone());
+        fs::create_dir_all(dir.clone()).unwrap();
+        {
+            let mut container = WriteBatchContainer::new();
+            for i in 0..100u8 {
+                container.put(vec![1, i].into_boxed_slice(), vec![i; 64].into_boxed_slice());
+            }
+            container.delete(vec![1, 5].into_boxed_slice());
+
+            let compressed_file = dir.join("compressed.flex").to_str().unwrap().to_string();
+            container.write_to_file(&compressed_file).await.unwrap();
+            let raw = fs::read(&compressed_file).unwrap();
+            assert!(raw.starts_with(&GZIP_MAGIC));
+
+            let mut ser = flexbuffers::FlexbufferSerializer::new();
+            container.serialize(&mut ser).unwrap();
+            assert!(raw.len() < ser.view().len());
+            let uncompressed_file = dir.join("uncompressed.flex").to_str().unwrap().to_string();
+            fs::write(&uncompressed_file, ser.view()).unwrap();
+
+            for file in vec![compressed_file, uncompressed_file].into_iter() {
+                let read = WriteBatchContainer::read_from_file(&file).await.unwrap();
+                assert_eq!(read.entries.len(), container.entries.len());
+                for (read_entry, entry) in read.entries.iter().zip(container.entries.iter()) {
+                    match (read_entry, entry) {
+                        (WriteBatchEntry::Put { key: k1, value: v1 }, WriteBatchEntry::Put { key: k2, value: v2 }) => {
+                            assert_eq!(k1, k2);
+                            assert_eq!(v1, v2);
+                        }
+                        (WriteBatchEntry::Delete { key: k1 }, WriteBatchEntry::Delete { key: k2 }) => assert_eq!(k1, k2),
+                        (a, b) => panic!("Entries don't match: {:?} vs {:?}", a, b)
+                    }
+                }
+            }
+        }
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[actix_rt::test]
+    async fn compact_remote_logs_test() {
+        let remote_store_path = env::current_dir().unwrap().join("test-compact_remote_logs_test-remote");
+        let restore_path = env::current_dir().unwrap().join("test-compact_remote_logs_test-restore");
+        let _ = fs::remove_dir_all(restore_path.clone());
+        {
+            let clock = Arc::new(TestClock::new(SystemTime::now()));
+            let config = MetaStoreConfig {
+                checkpoint_interval: Duration::from_secs(3600),
+                log_compaction_threshold: 3,
+                log_deletion_grace_period: Duration::from_secs(60),
+                clock: clock.clone(),
+                ..MetaStoreConfig::default()
+            };
+            let (remote_fs, meta_store) = RocksMetaStore::prepare_test_metastore_with_config("compact_remote_logs_test", config);
+            meta_store.upload_check_point().await.unwrap();
+            let checkpoint_time = meta_store.last_checkpoint_time.read().await.clone();
+            let logs_prefix = format!("{}-logs/", RocksMetaStore::meta_store_path(&checkpoint_time));
+
+            for i in 0..4 {
+                meta_store.create_schema(format!("foo{}", i), false).await.unwrap();
+                meta_store.run_upload().await.unwrap();
+            }
+            // Merged logs stay around during the grace period
+            assert_eq!(remote_fs.list(&logs_prefix).await.unwrap().len(), 4);
+            let first_merged = remote_fs.list(&logs_prefix).await.unwrap().into_iter().max_by_key(
+                |l| l[logs_prefix.len()..l.len() - ".flex".len()].parse::<u64>().unwrap()
+            ).unwrap();
+            let first_merged_bytes = fs::read(remote_store_path.join(&first_merged)).unwrap();
+
+            clock.advance(Duration::from_secs(61));
+            for i in 4..10 {
+                meta_store.create_schema(format!("foo{}", i), false).await.unwrap();
+                meta_store.run_upload().await.unwrap();
+            }
+            clock.advance(Duration::from_secs(61));
+            meta_store.delete_expired_logs().await;
+
+            // Two merged files of four logs each and two logs not compacted yet
+            let logs = remote_fs.list(&logs_prefix).await.unwrap();
+            assert_eq!(logs.len(), 4, "Expected compacted logs but found {:?}", logs);
+            // Already merged logs aren't merged again
+            assert_eq!(fs::read(remote_store_path.join(&first_merged)).unwrap(), first_merged_bytes);
+
+            let restore_fs = LocalDirRemoteFs::new(remote_store_path.clone(), restore_path.clone());
+            let restored = RocksMetaStore::load_from_remote(
+                restore_path.join("metastore").as_path(), restore_fs, MetaStoreConfig::default(), true
+            ).await.unwrap();
+            for i in 0..10 {
+                restored.get_schema(format!("foo{}", i)).await.unwrap();
+            }
+            assert_eq!(restored.get_schemas().await.unwrap().len(), 10);
+        }
+        let _ = fs::remove_dir_all(restore_path);
+        RocksMetaStore::cleanup_test_metastore("compact_remote_logs_test");
+    }
+
+    #[actix_rt::test]
+    async fn checkpoint_write_operations_test() {
+        {
+            let config = MetaStoreConfig {
+                checkpoint_interval: Duration::from_secs(3600),
+                checkpoint_write_operations: Some(3),
+                ..MetaStoreConfig::default()
+            };
+            let (remote_fs, meta_store) = RocksMetaStore::prepare_test_metastore_with_config("checkpoint_write_operations_test", config);
+            meta_store.upload_check_point().await.unwrap();
+            let initial_checkpoint_time = meta_store.last_checkpoint_time.read().await.clone();
+
+            for i in 0..2 {
+                meta_store.create_schema(format!("foo{}", i), false).await.unwrap();
+                meta_store.run_upload().await.unwrap();
+            }
+            assert_eq!(*meta_store.last_checkpoint_time.read().await, initial_checkpoint_time);
+
+            meta_store.create_schema("foo2".to_string(), false).await.unwrap();
+            meta_store.run_upload().await.unwrap();
+            let checkpoint_time = meta_store.last_checkpoint_time.read().await.clone();
+            assert!(checkpoint_time > initial_checkpoint_time);
+            assert_eq!(*meta_store.writes_since_checkpoint.read().await, 0);
+            let current_path = remote_fs.download_file("metastore-current").await.unwrap();
+            assert_eq!(fs::read_to_string(current_path).unwrap(), RocksMetaStore::meta_store_path(&checkpoint_time));
+
+            // Compressed copies are removed from the local checkpoint once uploaded
+            let local_checkpoint = env::current_dir().unwrap()
+                .join("test-checkpoint_write_operations_test-local")
+                .join(RocksMetaStore::meta_store_path(&checkpoint_time));
+            let local_files = fs::read_dir(local_checkpoint).unwrap()
+                .map(|f| f.unwrap().file_name().to_string_lossy().to_string())
+                .collect::<Vec<_>>();
+            assert!(!local_files.is_empty());
+            assert!(local_files.iter().all(|f| !f.ends_with(".gz")), "Unexpected compressed files in {:?}", local_files);
+        }
+        RocksMetaStore::cleanup_test_metastore("checkpoint_write_operations_test");
+    }
+
+    #[actix_rt::test]
+    async fn empty_remote_file_test() {
+        let remote_store_path = env::current_dir().unwrap().join("test-empty_remote_file_test-remote");
+        let restore_path = env::current_dir().unwrap().join("test-empty_remote_file_test-restore");
+        let _ = fs::remove_dir_all(restore_path.clone());
+        {
+            let (_, meta_store) = RocksMetaStore::prepare_test_metastore("empty_remote_file_test");
+            meta_store.create_schema("foo".to_string(), false).await.unwrap();
+            meta_store.upload_check_point().await.unwrap();
+
+            let checkpoint_time = meta_store.last_checkpoint_time.read().await.clone();
+            let checkpoint_path = RocksMetaStore::meta_store_path(&checkpoint_time);
+            let manifest = fs::read_dir(remote_store_path.join(&checkpoint_path)).unwrap()
+                .map(|f| f.unwrap().file_name().to_string_lossy().to_string())
+                .find(|f| f.starts_with("MANIFEST"))
+                .unwrap();
+            fs::write(remote_store_path.join(&checkpoint_path).join(&manifest), b"").unwrap();
+
+            let restore_fs = LocalDirRemoteFs::new(remote_store_path.clone(), restore_path.clone());
+            let err = RocksMetaStore::load_from_remote(
+                restore_path.join("metastore").as_path(), restore_fs, MetaStoreConfig::default(), false
+            ).await.err().unwrap();
+            assert_eq!(err.cause(), CubeErrorCause::Corruption);
+            assert!(err.to_string().contains(&format!("{}/{}", checkpoint_path, manifest)), "Unexpected error: {}", err);
+        }
+        let _ = fs::remove_dir_all(restore_path);
+        RocksMetaStore::cleanup_test_metastore("empty_remote_file_test");
+    }
+
     #[tokio::test]
     async fn cold_start_test() {
         let config = Config::test("cold_start_test");
@@ -5,12 +5,14 @@ use super::{BaseRocksSecondaryIndex, RocksTable, IndexId, RocksSecondaryIndex, P
 use byteorder::{WriteBytesExt, BigEndian};
 use crate::base_rocks_secondary_index;
 use crate::rocks_table_impl;
-use crate::table::Row;
+use crate::table::{Row, TableValue};
 use crate::metastore::{MetaStoreEvent, IdRow};
+use std::time::SystemTime;
 
 impl Partition {
     pub fn new(index_id: u64, min_value: Option<Row>, max_value: Option<Row>) -> Partition {
-        Partition{ index_id, min_value, max_value, parent_partition_id: None, active: true, main_table_row_count: 0 }
+        let now = SystemTime::now();
+        Partition{ index_id, min_value, max_value, parent_partition_id: None, active: true, main_table_row_count: 0, created_at: now, last_modified: now, last_activation: None }
     }
 
     pub fn child(&self, id: u64) -> Partition {
@@ -20,7 +22,10 @@ impl Partition {
             max_value: None,
             parent_partition_id: Some(id),
             active: false,
-            main_table_row_count: 0
+            main_table_row_count: 0,
+            created_at: SystemTime::now(),
+            last_modified: SystemTime::now(),
+            last_activation: None
         }
     }
 
@@ -44,6 +49,16 @@ impl Partition {
             parent_partition_id: self.parent_partition_id,
             active,
             main_table_row_count: self.main_table_row_count,
+            created_at: self.created_at,
+            last_modified: SystemTime::now(),
+            last_activation: self.last_activation
+        }
+    }
+
+    pub fn to_active_by_job(&self, active: bool, job_id: Option<u64>) -> Partition {
+        Partition {
+            last_activation: Some((SystemTime::now(), job_id)),
+            ..self.to_active(active)
         }
     }
 
@@ -55,6 +70,9 @@ impl Partition {
             parent_partition_id: self.parent_partition_id,
             active: self.active,
             main_table_row_count,
+            created_at: self.created_at,
+            last_modified: SystemTime::now(),
+            last_activation: self.last_activation
         }
     }
 
@@ -71,24 +89,38 @@ impl Partition {
     pub fn main_table_row_count(&self) -> u64 {
         self.main_table_row_count
     }
+
+    pub fn created_at(&self) -> SystemTime {
+        self.created_at
+    }
+
+    pub fn last_modified(&self) -> SystemTime {
+        self.last_modified
+    }
+
+    pub fn last_activation(&self) -> &Option<(SystemTime, Option<u64>)> {
+        &self.last_activation
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
 pub (crate) enum PartitionRocksIndex {
-    IndexId = 1
+    IndexId = 1,
+    MinValue = 2
 }
 
 rocks_table_impl!(
     Partition,
     PartitionRocksTable,
     TableId::Partitions,
-    { vec![Box::new(PartitionRocksIndex::IndexId)] },
+    { vec![Box::new(PartitionRocksIndex::IndexId), Box::new(PartitionRocksIndex::MinValue)] },
     DeletePartition
 );
 
 #[derive(Hash, Clone, Debug)]
 pub enum PartitionIndexKey {
-    ByIndexId(u64)
+    ByIndexId(u64),
+    ByMinValue(u64, Option<Row>)
 }
 
 base_rocks_secondary_index!(Partition, PartitionRocksIndex);
@@ -96,7 +128,8 @@ base_rocks_secondary_index!(Partition, PartitionRocksIndex);
 impl RocksSecondaryIndex<Partition, PartitionIndexKey> for PartitionRocksIndex {
     fn typed_key_by(&self, row: &Partition) -> PartitionIndexKey {
         match self {
-            PartitionRocksIndex::IndexId => PartitionIndexKey::ByIndexId(row.index_id)
+            PartitionRocksIndex::IndexId => PartitionIndexKey::ByIndexId(row.index_id),
+            PartitionRocksIndex::MinValue => PartitionIndexKey::ByMinValue(row.index_id, row.min_value.clone())
         }
     }
 
@@ -107,12 +140,32 @@ impl RocksSecondaryIndex<Partition, PartitionIndexKey> for PartitionRocksIndex {
                 buf.write_u64::<BigEndian>(*index_id).unwrap();
                 buf
             }
+            PartitionIndexKey::ByMinValue(index_id, min_value) => {
+                let mut buf = Vec::with_capacity(64);
+                buf.write_u64::<BigEndian>(*index_id).unwrap();
+                match min_value {
+                    None => buf.write_u8(0).unwrap(),
+                    Some(row) => {
+                        buf.write_u8(1).unwrap();
+                        write_ordered_row(&mut buf, row);
+                    }
+                }
+                buf
+            }
         }
     }
 
     fn is_unique(&self) -> bool {
         match self {
-            PartitionRocksIndex::IndexId => false
+            PartitionRocksIndex::IndexId => false,
+            PartitionRocksIndex::MinValue => false
+        }
+    }
+
+    fn is_ordered(&self) -> bool {
+        match self {
+            PartitionRocksIndex::IndexId => false,
+            PartitionRocksIndex::MinValue => true
         }
     }
 
@@ -120,3 +173,49 @@ impl RocksSecondaryIndex<Partition, PartitionIndexKey> for PartitionRocksIndex {
         *self as IndexId
     }
 }
+
+// Encodes a row so that byte order matches `TableValue` order and no encoding is a prefix of another
+fn write_ordered_row(buf: &mut Vec<u8>, row: &Row) {
+    for value in row.values().iter() {
+        buf.write_u8(1).unwrap();
+        match value {
+            TableValue::Null => buf.write_u8(0).unwrap(),
+            TableValue::String(v) => {
+                buf.write_u8(1).unwrap();
+                write_ordered_bytes(buf, v.as_bytes());
+            }
+            TableValue::Int(v) => {
+                buf.write_u8(2).unwrap();
+                buf.write_u64::<BigEndian>(*v as u64 ^ (1 << 63)).unwrap();
+            }
+            TableValue::Decimal(v) => {
+                buf.write_u8(3).unwrap();
+                write_ordered_bytes(buf, v.as_bytes());
+            }
+            TableValue::Bytes(v) => {
+                buf.write_u8(4).unwrap();
+                write_ordered_bytes(buf, v.as_slice());
+            }
+            TableValue::Timestamp(v) => {
+                buf.write_u8(5).unwrap();
+                buf.write_u64::<BigEndian>(v.get_time_stamp() as u64 ^ (1 << 63)).unwrap();
+            }
+            TableValue::Boolean(v) => {
+                buf.write_u8(6).unwrap();
+                buf.write_u8(*v as u8).unwrap();
+            }
+        }
+    }
+    buf.write_u8(0).unwrap();
+}
+
+fn write_ordered_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+    for b in bytes.iter() {
+        buf.write_u8(*b).unwrap();
+        if *b == 0 {
+            buf.write_u8(0xff).unwrap();
+        }
+    }
+    buf.write_u8(0).unwrap();
+    buf.write_u8(0).unwrap();
+}
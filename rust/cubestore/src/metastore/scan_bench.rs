@@ -0,0 +1,39 @@
+//! Chunk table scans for `tests/table_scan_allocations.rs` and `benches/table_scan.rs`.
+//! Those run in their own targets as the allocation test replaces the global allocator.
+
+use super::*;
+
+pub fn prepare_chunks_db(path: impl AsRef<Path>, rows: usize) -> Result<Arc<DB>, CubeError> {
+    let _ = DB::destroy(&Options::default(), path.as_ref());
+    let mut opts = Options::default();
+    opts.create_if_missing(true);
+    opts.set_prefix_extractor(rocksdb::SliceTransform::create_fixed_prefix(13));
+    let db = Arc::new(DB::open(&opts, path)?);
+
+    let table = ChunkRocksTable::new(db.clone());
+    let mut batch_pipe = BatchPipe::new(db.as_ref());
+    for i in 0..rows {
+        table.insert(Chunk::new((i % 100) as u64, i), &mut batch_pipe)?;
+    }
+    batch_pipe.batch_write_rows()?;
+    Ok(db)
+}
+
+pub fn scan_chunks(db: &Arc<DB>) -> Result<Vec<IdRow<Chunk>>, CubeError> {
+    ChunkRocksTable::new(db.clone()).all_rows()
+}
+
+// Scan as it was done before rows were deserialized from the iterator's borrowed slices
+pub fn scan_chunks_copying(db: &Arc<DB>) -> Result<Vec<IdRow<Chunk>>, CubeError> {
+    let table = ChunkRocksTable::new(db.clone());
+    let key_min = RowKey::Table(TableId::Chunks, 0);
+    let mut res = Vec::new();
+    for (key, value) in db.prefix_iterator(&key_min.to_bytes()[0..get_fixed_prefix()]) {
+        if let RowKey::Table(TableId::Chunks, row_id) = RowKey::from_bytes(&key)? {
+            res.push(table.deserialize_id_row(row_id, &value)?);
+        } else {
+            break;
+        }
+    }
+    Ok(res)
+}
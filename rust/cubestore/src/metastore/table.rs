@@ -58,6 +58,12 @@ impl Table {
         &self.columns
     }
 
+    pub fn add_column(&self, column: Column) -> Table {
+        let mut table = self.clone();
+        table.columns.push(column.replace_index(self.columns.len()));
+        table
+    }
+
     pub fn get_schema_id(&self) -> u64 {
         self.schema_id
     }
@@ -104,18 +110,20 @@ rocks_table_impl!(
     Table,
     TableRocksTable,
     TableId::Tables,
-    { vec![Box::new(TableRocksIndex::Name)]},
+    { vec![Box::new(TableRocksIndex::Name), Box::new(TableRocksIndex::SchemaId)]},
     DeleteTable
 );
 
 #[derive(Clone, Copy, Debug)]
 pub(crate) enum TableRocksIndex {
     Name = 1,
+    SchemaId = 2,
 }
 
 #[derive(Hash, Clone, Debug)]
 pub enum TableIndexKey {
-    ByName(u64, String)
+    ByName(u64, String),
+    BySchemaId(u64)
 }
 
 base_rocks_secondary_index!(Table, TableRocksIndex);
@@ -124,6 +132,7 @@ impl RocksSecondaryIndex<Table, TableIndexKey> for TableRocksIndex {
     fn typed_key_by(&self, row: &Table) -> TableIndexKey {
         match self {
             TableRocksIndex::Name => TableIndexKey::ByName(row.schema_id, row.table_name.to_string()),
+            TableRocksIndex::SchemaId => TableIndexKey::BySchemaId(row.schema_id),
         }
     }
 
@@ -135,12 +144,18 @@ impl RocksSecondaryIndex<Table, TableIndexKey> for TableRocksIndex {
                 buf.write_all(table_name.as_bytes()).unwrap();
                 buf
             }
+            TableIndexKey::BySchemaId(schema_id) => {
+                let mut buf = Vec::new();
+                buf.write_u64::<BigEndian>(*schema_id).unwrap();
+                buf
+            }
         }
     }
 
     fn is_unique(&self) -> bool {
         match self {
             TableRocksIndex::Name => true,
+            TableRocksIndex::SchemaId => false,
         }
     }
 
@@ -33,7 +33,7 @@ use datafusion::logical_plan::LogicalPlan;
 use crate::store::DataFrame;
 use std::time::SystemTime;
 use crate::queryplanner::query_executor::batch_to_dataframe;
-use log::{debug};
+use log::{debug, error};
 
 #[automock]
 #[async_trait]
@@ -132,8 +132,16 @@ impl MetaStoreSchemaProvider {
 
 impl SchemaProvider for MetaStoreSchemaProvider {
     fn get_table_meta(&self, name: &str) -> Option<SchemaRef> {
-        let res = self.tables.get(name).map(|table| {
-            Arc::new(Schema::new(table.table.get_row().get_columns().iter().map(|c| c.clone().into()).collect::<Vec<_>>()))
+        let res = self.tables.get(name).and_then(|table| {
+            let fields = table.table.get_row().get_columns().iter().map(|c| c.arrow_field()).collect::<Result<Vec<_>, _>>();
+            match fields {
+                Ok(fields) => Some(Arc::new(Schema::new(fields))),
+                // SchemaProvider can't return errors so table is reported as missing
+                Err(e) => {
+                    error!("Can't plan query for table {}: {}", name, e);
+                    None
+                }
+            }
         });
         res.or_else(|| self.information_schema_context.state.get_table_meta(name))
     }
@@ -278,7 +278,7 @@ impl CubeTable {
         remote_to_local_names: HashMap<String, String>,
         worker_partition_id: Option<u64>,
     ) -> Result<Self, CubeError> {
-        let schema = Arc::new(Schema::new(index_snapshot.table().get_row().get_columns().iter().map(|c| c.clone().into()).collect::<Vec<_>>()));
+        let schema = Arc::new(Schema::new(index_snapshot.table().get_row().get_columns().iter().map(|c| c.arrow_field()).collect::<Result<Vec<_>, _>>()?));
         Ok(Self { index_snapshot, schema, remote_to_local_names, worker_partition_id })
     }
 
@@ -0,0 +1,113 @@
+use async_trait::async_trait;
+use crate::remotefs::{RemoteFs, RemoteFile};
+use crate::CubeError;
+use std::sync::Arc;
+use log::{warn};
+
+// Both targets are expected to share the same local dir as uploads read local files from there
+pub struct MirroredRemoteFs {
+    primary: Arc<dyn RemoteFs>,
+    replica: Arc<dyn RemoteFs>
+}
+
+impl MirroredRemoteFs {
+    pub fn new(primary: Arc<dyn RemoteFs>, replica: Arc<dyn RemoteFs>) -> Arc<MirroredRemoteFs> {
+        Arc::new(MirroredRemoteFs { primary, replica })
+    }
+}
+
+#[async_trait]
+impl RemoteFs for MirroredRemoteFs {
+    async fn upload_file(&self, remote_path: &str) -> Result<(), CubeError> {
+        self.primary.upload_file(remote_path).await?;
+        self.replica.upload_file(remote_path).await
+    }
+
+    async fn download_file(&self, remote_path: &str) -> Result<String, CubeError> {
+        match self.primary.download_file(remote_path).await {
+            Ok(path) => Ok(path),
+            Err(e) => {
+                warn!("Primary remote fs failed to download {}, falling back to replica: {}", remote_path, e);
+                self.replica.download_file(remote_path).await
+            }
+        }
+    }
+
+    async fn delete_file(&self, remote_path: &str) -> Result<(), CubeError> {
+        self.primary.delete_file(remote_path).await?;
+        self.replica.delete_file(remote_path).await
+    }
+
+    async fn list(&self, remote_prefix: &str) -> Result<Vec<String>, CubeError> {
+        match self.primary.list(remote_prefix).await {
+            Ok(list) => Ok(list),
+            Err(e) => {
+                warn!("Primary remote fs failed to list {}, falling back to replica: {}", remote_prefix, e);
+                self.replica.list(remote_prefix).await
+            }
+        }
+    }
+
+    async fn list_with_metadata(&self, remote_prefix: &str) -> Result<Vec<RemoteFile>, CubeError> {
+        match self.primary.list_with_metadata(remote_prefix).await {
+            Ok(list) => Ok(list),
+            Err(e) => {
+                warn!("Primary remote fs failed to list {}, falling back to replica: {}", remote_prefix, e);
+                self.replica.list_with_metadata(remote_prefix).await
+            }
+        }
+    }
+
+    async fn local_path(&self) -> String {
+        self.primary.local_path().await
+    }
+
+    async fn local_file(&self, remote_path: &str) -> Result<String, CubeError> {
+        self.primary.local_file(remote_path).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::remotefs::{RemoteFs, LocalDirRemoteFs};
+    use crate::remotefs::mirrored::MirroredRemoteFs;
+    use std::{env, fs};
+
+    #[tokio::test]
+    async fn mirrored_upload_and_failover() {
+        let local_dir = env::current_dir().unwrap().join("mirrored-remote-fs-local");
+        let primary_dir = env::current_dir().unwrap().join("mirrored-remote-fs-primary");
+        let replica_dir = env::current_dir().unwrap().join("mirrored-remote-fs-replica");
+        let _ = fs::remove_dir_all(local_dir.clone());
+        let _ = fs::remove_dir_all(primary_dir.clone());
+        let _ = fs::remove_dir_all(replica_dir.clone());
+
+        let remote_fs = MirroredRemoteFs::new(
+            LocalDirRemoteFs::new(primary_dir.clone(), local_dir.clone()),
+            LocalDirRemoteFs::new(replica_dir.clone(), local_dir.clone())
+        );
+
+        let local_file = remote_fs.local_file("foo/bar.txt").await.unwrap();
+        fs::write(&local_file, "test data").unwrap();
+        remote_fs.upload_file("foo/bar.txt").await.unwrap();
+        assert_eq!(fs::read_to_string(primary_dir.join("foo/bar.txt")).unwrap(), "test data");
+        assert_eq!(fs::read_to_string(replica_dir.join("foo/bar.txt")).unwrap(), "test data");
+        assert_eq!(remote_fs.list("foo").await.unwrap(), vec!["foo/bar.txt".to_string()]);
+
+        // Primary lost the file: download should be served by the replica
+        fs::remove_file(primary_dir.join("foo/bar.txt")).unwrap();
+        fs::remove_file(&local_file).unwrap();
+        let downloaded = remote_fs.download_file("foo/bar.txt").await.unwrap();
+        assert_eq!(downloaded, local_file);
+        assert_eq!(fs::read_to_string(&downloaded).unwrap(), "test data");
+
+        fs::write(primary_dir.join("foo/bar.txt"), "test data").unwrap();
+        remote_fs.delete_file("foo/bar.txt").await.unwrap();
+        assert!(!primary_dir.join("foo/bar.txt").exists());
+        assert!(!replica_dir.join("foo/bar.txt").exists());
+
+        let _ = fs::remove_dir_all(local_dir.clone());
+        let _ = fs::remove_dir_all(primary_dir.clone());
+        let _ = fs::remove_dir_all(replica_dir.clone());
+    }
+}
@@ -1,4 +1,5 @@
 pub mod s3;
+pub mod mirrored;
 
 use async_trait::async_trait;
 use crate::CubeError;
@@ -70,7 +71,9 @@ impl RemoteFs for LocalDirRemoteFs {
         fs::copy(
             dir.as_path().join(remote_path),
             dest.clone()
-        ).await?;
+        ).await.map_err(
+            |e| CubeError::remote_storage(format!("Error during uploading of {}: {}", remote_path, e))
+        )?;
         Ok(())
     }
 
@@ -86,7 +89,7 @@ impl RemoteFs for LocalDirRemoteFs {
                 remote_dir.as_path().join(remote_path),
                 local
             ).await.map_err(
-                |e| CubeError::internal(format!("Error during downloading of {}: {}", remote_path, e))
+                |e| CubeError::remote_storage(format!("Error during downloading of {}: {}", remote_path, e))
             )?;
         }
         Ok(path)
@@ -101,8 +104,10 @@ impl RemoteFs for LocalDirRemoteFs {
 
         let dir = self.dir.write().await;
         let local = dir.as_path().join(remote_path);
-        fs::remove_file(local.clone()).await?;
-        Self::remove_empty_paths(dir.clone(), local.clone()).await?;
+        if fs::metadata(local.clone()).await.is_ok() {
+            fs::remove_file(local.clone()).await?;
+            Self::remove_empty_paths(dir.clone(), local.clone()).await?;
+        }
 
         Ok(())
     }
@@ -30,7 +30,7 @@ impl RemoteFs for S3RemoteFs {
         debug!("Uploading {}", remote_path);
         let status_code = self.bucket.put_object_stream(self.dir.read().await.as_path().join(remote_path), format!("/{}", remote_path)).await?;
         if status_code != 200 {
-            return Err(CubeError::user(format!("S3 upload returned non OK status: {}", status_code)));
+            return Err(CubeError::remote_storage(format!("S3 upload returned non OK status: {}", status_code)));
         }
         Ok(())
     }
@@ -44,7 +44,7 @@ impl RemoteFs for S3RemoteFs {
             let mut output_file = std::fs::File::create(path.as_str())?;
             let status_code = self.bucket.get_object_stream(S3RemoteFs::s3_path(remote_path), &mut output_file).await?;
             if status_code != 200 {
-                return Err(CubeError::user(format!("S3 download returned non OK status: {}", status_code)));
+                return Err(CubeError::remote_storage(format!("S3 download returned non OK status: {}", status_code)));
             }
         }
         Ok(path)
@@ -54,7 +54,7 @@ impl RemoteFs for S3RemoteFs {
         debug!("Deleting {}", remote_path);
         let (_, status_code) = self.bucket.delete_object(S3RemoteFs::s3_path(remote_path)).await?;
         if status_code != 204 {
-            return Err(CubeError::user(format!("S3 delete returned non OK status: {}", status_code)));
+            return Err(CubeError::remote_storage(format!("S3 delete returned non OK status: {}", status_code)));
         }
 
         let dir = self.dir.write().await;
@@ -62,7 +62,7 @@ impl SqlServiceImpl {
         }
         if external {
             let listener = self.cluster.job_result_listener();
-            let table = self.db.create_table(schema_name, table_name, columns_to_set, location, Some(ImportFormat::CSV), indexes_to_create).await?;
+            let table = self.db.create_table(schema_name, table_name, columns_to_set, location, Some(ImportFormat::CSV), indexes_to_create, vec![]).await?;
             listener.wait_for_job_result(RowKey::Table(TableId::Tables, table.get_id()), JobType::TableImport).await?;
             let wal_listener = self.cluster.job_result_listener();
             let wals = self.db.get_wals_for_table(table.get_id()).await?;
@@ -76,7 +76,7 @@ impl SqlServiceImpl {
 
             Ok(table)
         } else {
-            self.db.create_table(schema_name, table_name, columns_to_set, None, None, indexes_to_create).await
+            self.db.create_table(schema_name, table_name, columns_to_set, None, None, indexes_to_create, vec![]).await
         }
     }
 
@@ -210,7 +210,7 @@ impl SqlService for SqlServiceImpl {
                     }
                     ObjectType::Table => {
                         let table = self.db.get_table(names[0].0[0].to_string(), names[0].0[1].to_string()).await?;
-                        self.db.drop_table(table.get_id()).await?;
+                        self.db.drop_table(table.get_id(), false).await?;
                     }
                     _ => return Err(CubeError::user("Unsupported drop operation".to_string()))
                 }
@@ -269,6 +269,9 @@ fn convert_columns_type(columns: &Vec<ColumnDef>) -> Result<Vec<Column>, CubeErr
                                            DataType::Float(_) | DataType::Real
                                            | DataType::Double => { ColumnType::Decimal }
                                            DataType::Timestamp => { ColumnType::Timestamp }
+                                           DataType::Custom(custom) if custom.to_string().to_lowercase() == "json" => {
+                                               ColumnType::Json
+                                           }
                                            DataType::Custom(custom) => {
                                                return Err(CubeError::user(format!("Custom type '{}' is not supported", custom)));
                                            }
@@ -327,6 +330,7 @@ fn extract_data(cell: &Expr, column: &Vec<&Column>, i: usize) -> Result<TableVal
                 TableValue::Int(val_int.unwrap())
             }
             ColumnType::Decimal => { return Err(CubeError::user("Decimal type not implemented.".to_string())); }
+            ColumnType::Float => { return Err(CubeError::user("Float type not implemented.".to_string())); }
             ColumnType::Bytes => {
                 // TODO What we need to do with Bytes, now it  just convert each element of string to u8 item of Vec<u8>
                 let val = if let Value::Number(v) = d {
@@ -356,6 +360,17 @@ fn extract_data(cell: &Expr, column: &Vec<&Column>, i: usize) -> Result<TableVal
                     x => return Err(CubeError::user(format!("Can't parse boolean from, {:?}", x)))
                 }
             }
+            ColumnType::Json => {
+                let val = if let Value::SingleQuotedString(v) = d {
+                    v
+                } else {
+                    return Err(CubeError::user(format!("Single quoted JSON string is expected but {:?} found", cell)));
+                };
+                if let Err(e) = serde_json::from_str::<serde_json::Value>(val) {
+                    return Err(CubeError::user(format!("Invalid JSON value {:?}: {}", val, e)));
+                }
+                TableValue::String(val.to_string())
+            }
         }
     };
     Ok(res)
@@ -389,7 +404,7 @@ mod tests {
 
         {
             let remote_fs = LocalDirRemoteFs::new(PathBuf::from(store_path.clone()), PathBuf::from(remote_store_path.clone()));
-            let meta_store = RocksMetaStore::new(path, remote_fs.clone());
+            let meta_store = RocksMetaStore::new(path, remote_fs.clone()).unwrap();
             let store = WALStore::new(meta_store.clone(), remote_fs.clone(), 10);
             let service = SqlServiceImpl::new(
                 meta_store,
@@ -416,7 +431,7 @@ mod tests {
         let _ = fs::remove_dir_all(remote_store_path.clone());
         {
             let remote_fs = LocalDirRemoteFs::new(PathBuf::from(store_path.clone()), PathBuf::from(remote_store_path.clone()));
-            let meta_store = RocksMetaStore::new(path, remote_fs.clone());
+            let meta_store = RocksMetaStore::new(path, remote_fs.clone()).unwrap();
             let store = WALStore::new(meta_store.clone(), remote_fs.clone(), 10);
             let service = SqlServiceImpl::new(meta_store, store, Arc::new(MockQueryPlanner::new()), Arc::new(MockQueryExecutor::new()), Arc::new(MockCluster::new()));
             let i = service.exec_query("CREATE SCHEMA Foo").await.unwrap();
@@ -488,6 +503,25 @@ mod tests {
         }).await;
     }
 
+    #[tokio::test]
+    async fn insert_json() {
+        Config::run_test("insert_json", async move |services| {
+            let service = services.sql_service;
+
+            let _ = service.exec_query("CREATE SCHEMA Foo").await.unwrap();
+
+            let _ = service.exec_query("CREATE TABLE Foo.Events (id int, payload json)").await.unwrap();
+
+            service.exec_query(
+                "INSERT INTO Foo.Events (id, payload) VALUES (1, '{\"name\": \"click\", \"tags\": [1, 2]}'), (2, '[]'), (3, NULL)"
+            ).await.unwrap();
+
+            let res = service.exec_query("INSERT INTO Foo.Events (id, payload) VALUES (4, '{\"name\": ')").await;
+            assert!(res.is_err());
+            assert!(res.unwrap_err().message.contains("Invalid JSON"));
+        }).await;
+    }
+
     #[tokio::test]
     async fn select_test() {
         Config::run_test("select", async move |services| {
@@ -1,6 +1,6 @@
 use async_trait::async_trait;
 use crate::CubeError;
-use crate::metastore::{MetaStore};
+use crate::metastore::{MetaStore, CompactionInput, IndexStats};
 use std::sync::Arc;
 use crate::table::parquet::ParquetTableStore;
 use crate::store::ChunkDataStore;
@@ -35,8 +35,10 @@ impl CompactionServiceImpl {
 #[async_trait]
 impl CompactionService for CompactionServiceImpl {
     async fn compact(&self, partition_id: u64) -> Result<(), CubeError> {
-        let chunks = self.meta_store.get_chunks_by_partition(partition_id).await?;
-        let (partition, index) = self.meta_store.get_partition_for_compaction(partition_id).await?;
+        let (partition, index, chunks) = match self.meta_store.get_compaction_input(partition_id, None).await? {
+            CompactionInput::Compact(partition, index, chunks) => (partition, index, chunks),
+            CompactionInput::NotWorthCompacting { .. } => return Ok(())
+        };
         let partition_id = partition.get_id();
         let chunks_row_count = chunks.iter().map(|c| c.get_row().get_row_count()).sum::<u64>();
         let total_count = partition.get_row().main_table_row_count() + chunks_row_count;
@@ -54,6 +56,7 @@ impl CompactionService for CompactionServiceImpl {
         }
         let sort_key_size = index.get_row().sort_key_size();
         rows.sort_by(|a, b| a.sort_key(sort_key_size).cmp(&b.sort_key(sort_key_size)));
+        let new_rows_stats = IndexStats::from_rows(index.get_id(), index.get_row().columns().len(), &rows);
 
         let store = ParquetTableStore::new(index.get_row().clone(), 16384); // TODO config
         let old_partition_local = if let Some(f) = partition.get_row().get_full_name(partition.get_id()) {
@@ -97,7 +100,9 @@ impl CompactionService for CompactionServiceImpl {
                 } else {
                     (c, (Some(min), Some(max)))
                 }
-            }).collect::<Vec<_>>()
+            }).collect::<Vec<_>>(),
+            Some(new_rows_stats),
+            None
         ).await?;
 
         Ok(())
@@ -125,6 +130,7 @@ mod tests {
             cols.clone(),
             None,
             None,
+            vec![],
             vec![]
         ).await.unwrap();
         metastore.get_default_index(1).await.unwrap();
@@ -159,6 +165,10 @@ mod tests {
         let partition_2 = metastore.get_partition(3).await.unwrap();
         assert_eq!(partition_2.get_row().get_min_val(), &Some(Row::new(vec![TableValue::String("foo4".to_string())])));
         assert_eq!(partition_2.get_row().get_max_val(), &None);
+        let stats = metastore.get_index_stats(1).await.unwrap().unwrap();
+        assert_eq!(stats.get_row().columns()[0].min(), &Some(TableValue::String("foo0".to_string())));
+        assert_eq!(stats.get_row().columns()[0].max(), &Some(TableValue::String("foo9".to_string())));
+        assert_eq!(stats.get_row().columns()[0].null_count(), 0);
         RocksMetaStore::cleanup_test_metastore("compaction");
     }
 }
\ No newline at end of file
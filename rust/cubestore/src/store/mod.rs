@@ -68,13 +68,13 @@ impl DataFrame {
     }
 
     pub fn to_execution_plan(&self, columns: &Vec<Column>) -> Result<Arc<dyn ExecutionPlan + Send + Sync>, CubeError> {
-        let schema = Arc::new(Schema::new(columns.iter().map(|c| c.clone().into()).collect::<Vec<_>>()));
+        let schema = Arc::new(Schema::new(columns.iter().map(|c| c.arrow_field()).collect::<Result<Vec<_>, _>>()?));
 
         let mut column_values: Vec<Arc<dyn Array>> = Vec::with_capacity(schema.fields().len());
 
         for c in columns.iter() {
             match c.get_column_type() {
-                ColumnType::String => {
+                ColumnType::String | ColumnType::Json => {
                     let mut column = StringBuilder::new(self.data.len());
                     for i in 0..self.data.len() {
                         let value = &self.data[i].values()[c.get_index()];
@@ -98,7 +98,7 @@ impl DataFrame {
                     }
                     column_values.push(Arc::new(column.finish()));
                 }
-                _ => unimplemented!()
+                x => return Err(CubeError::internal(format!("Column type {:?} of column {} is not supported in execution plans", x, c.get_name())))
             }
         }
 
@@ -342,7 +342,7 @@ mod tests {
 
         {
             let remote_fs = LocalDirRemoteFs::new(PathBuf::from(store_path.clone()), PathBuf::from(remote_store_path.clone()));
-            let store = WALStore::new( RocksMetaStore::new(path, remote_fs.clone()), remote_fs.clone(), 10);
+            let store = WALStore::new( RocksMetaStore::new(path, remote_fs.clone()).unwrap(), remote_fs.clone(), 10);
 
             let col = vec![Column::new("foo_int".to_string(), ColumnType::Int, 0), Column::new("foo".to_string(), ColumnType::String, 1), Column::new("boo".to_string(), ColumnType::String, 2)];
             let first_rows = (0..35).map(|i| Row::new(vec![TableValue::Int(i), TableValue::String(format!("Foo {}", i)), TableValue::String(format!("Boo {}", i))])).collect::<Vec<_>>();
@@ -378,7 +378,7 @@ mod tests {
         let _ =  fs::remove_dir_all(chunk_remote_store_path.clone());
         {
             let remote_fs = LocalDirRemoteFs::new(PathBuf::from(chunk_store_path.clone()), PathBuf::from(chunk_remote_store_path.clone()));
-            let meta_store = RocksMetaStore::new(path, remote_fs.clone());
+            let meta_store = RocksMetaStore::new(path, remote_fs.clone()).unwrap();
             let wal_store = WALStore::new(meta_store.clone(), remote_fs.clone(), 10);
             let chunk_store = ChunkStore::new(meta_store.clone(), remote_fs.clone(), wal_store.clone(), 10);
 
@@ -387,7 +387,7 @@ mod tests {
 
             let data_frame = DataFrame::new(col.clone(), first_rows);
             meta_store.create_schema("foo".to_string(), false).await.unwrap();
-            let table = meta_store.create_table("foo".to_string(), "bar".to_string(), col.clone(), None, None, vec![]).await.unwrap();
+            let table = meta_store.create_table("foo".to_string(), "bar".to_string(), col.clone(), None, None, vec![], vec![]).await.unwrap();
 
             let _ = wal_store.add_wal(table.clone(), data_frame).await;
             let wal = IdRow::new(1, WAL::new(1, 10));
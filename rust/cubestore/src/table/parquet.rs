@@ -155,19 +155,19 @@ impl<'a> RowParquetReader<'a> {
         let parquet_reader = SerializedFileReader::new(file)?;
 
         let column_with_buffer = columns_to_read.unwrap_or(table.get_columns()).iter()
-            .map(|c| (
+            .map(|c| Ok((
                 c,
                 c.get_index(),
                 match c.get_column_type() {
-                    ColumnType::String => ColumnAccessor::Bytes(vec![ByteArray::new(); 16384]),
+                    ColumnType::String | ColumnType::Json => ColumnAccessor::Bytes(vec![ByteArray::new(); 16384]),
                     ColumnType::Bytes => ColumnAccessor::Bytes(vec![ByteArray::new(); 16384]),
                     ColumnType::Int => ColumnAccessor::Int(vec![0; 16384]),
                     ColumnType::Timestamp => ColumnAccessor::Int(vec![0; 16384]),
                     ColumnType::Boolean => ColumnAccessor::Boolean(vec![false; 16384]),
-                    x => panic!("Column type is not supported: {:?}", x)
+                    x => return Err(CubeError::internal(format!("Column type {:?} of column {} is not supported in parquet reader", x, c.get_name())))
                 },
                 Some(vec![0; 16384])
-            )).collect::<Vec<_>>();
+            ))).collect::<Result<Vec<_>, CubeError>>()?;
 
         Ok(RowParquetReader {
             parquet_reader,
@@ -212,7 +212,7 @@ impl<'a> RowParquetReader<'a> {
             match def_levels {
                 Some(levels) => {
                     match col.get_column_type() {
-                        ColumnType::String => {
+                        ColumnType::String | ColumnType::Json => {
                             if let ColumnAccessor::Bytes(buffer) = &column_accessor {
                                 for i in 0..values_read {
                                     if levels[i] == 1 {
@@ -476,7 +476,7 @@ impl RowParquetWriter {
                         let def_levels = self.get_def_levels(batch_size, row_batch_index, column_index, rows_in_group, column_values.len());
                         typed.write_batch_with_statistics(&column_values, def_levels.as_ref().map(|b| b.as_slice()), None, &min, &max, None, None)?;
                     }
-                    _ => panic!("Unsupported writer")
+                    _ => return Err(CubeError::internal("Unsupported parquet column writer".to_string()))
                 };
 
                 row_group_writer.close_column(col_writer)?;
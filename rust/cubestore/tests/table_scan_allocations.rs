@@ -0,0 +1,51 @@
+use cubestore::metastore::scan_bench::{prepare_chunks_db, scan_chunks, scan_chunks_copying};
+use rocksdb::{DB, Options};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::env;
+
+// Counts allocations per thread so concurrently running tests don't add noise
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = Cell::new(0);
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|c| c.set(c.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn count_allocations<R>(f: impl FnOnce() -> R) -> (R, usize) {
+    let before = ALLOCATIONS.with(|c| c.get());
+    let res = f();
+    (res, ALLOCATIONS.with(|c| c.get()) - before)
+}
+
+// Copying scan allocates a boxed key and value for every row on top of the deserialized row
+#[test]
+fn table_scan_allocations_test() {
+    let rows = 10000;
+    let path = env::current_dir().unwrap().join("test-table_scan_allocations_test");
+    {
+        let db = prepare_chunks_db(&path, rows).unwrap();
+        let (borrowed, borrowed_allocations) = count_allocations(|| scan_chunks(&db).unwrap());
+        let (copied, copying_allocations) = count_allocations(|| scan_chunks_copying(&db).unwrap());
+        assert_eq!(borrowed.len(), rows);
+        assert_eq!(borrowed, copied);
+        assert!(
+            borrowed_allocations + 2 * rows <= copying_allocations,
+            "Borrowed scan made {} allocations while copying one made {}", borrowed_allocations, copying_allocations
+        );
+    }
+    let _ = DB::destroy(&Options::default(), path);
+}